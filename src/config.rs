@@ -0,0 +1,106 @@
+use crate::keys::Keymap;
+use crate::results::LogFormat;
+use crate::thok::CaretStyle;
+use directories::ProjectDirs;
+use serde::Deserialize;
+use std::{error::Error, fs};
+
+/// user preferences read from `config.toml` in the thokr config directory.
+/// command line flags take precedence over anything set here
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// show the live wpm, accuracy and progress line while typing
+    pub live_stats: bool,
+    /// formats test results are logged in after each test
+    pub logs: Vec<LogFormat>,
+    /// keyboard layout being practised, by name or path to a json layout
+    pub layout: String,
+    /// translate keys typed on a qwerty system into `layout`
+    pub remap: bool,
+    /// draw the keyboard under the prompt while typing
+    pub keyboard: bool,
+    /// wpm the focus key of a lesson needs before the next key unlocks
+    pub lesson_wpm: f64,
+    /// accuracy the focus key of a lesson needs before the next key unlocks
+    pub lesson_accuracy: f64,
+    /// count down before each test and start the timer when it runs out
+    pub countdown: bool,
+    /// restart the test as soon as the first word is mistyped
+    pub restart_on_typo: bool,
+    /// how the caret is drawn
+    pub caret: CaretStyle,
+    /// glide the caret between characters instead of jumping
+    pub smooth_caret: bool,
+    /// place the terminal's own cursor on the caret
+    pub terminal_cursor: bool,
+    /// mark mistakes with more than colour, leave out the skull art and
+    /// print a plain text summary of each test on exit
+    pub accessible: bool,
+    /// keys bound to each action, by action name
+    pub keys: Keymap,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            live_stats: true,
+            logs: vec![LogFormat::Csv],
+            layout: "qwerty".to_string(),
+            remap: false,
+            keyboard: false,
+            lesson_wpm: 35.0,
+            lesson_accuracy: 95.0,
+            countdown: false,
+            restart_on_typo: false,
+            caret: CaretStyle::Underline,
+            smooth_caret: false,
+            terminal_cursor: false,
+            accessible: false,
+            keys: Keymap::default(),
+        }
+    }
+}
+
+impl Config {
+    /// loads the config file, falling back to the defaults when there is none
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let Some(proj_dirs) = ProjectDirs::from("", "", "thokr") else {
+            return Ok(Self::default());
+        };
+        let config_path = proj_dirs.config_dir().join("config.toml");
+
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(toml::from_str(&fs::read_to_string(config_path)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert!(config.live_stats);
+        assert_eq!(config.logs, vec![LogFormat::Csv]);
+
+        let config: Config = toml::from_str("live_stats = false").unwrap();
+        assert!(!config.live_stats);
+        assert_eq!(config.layout, "qwerty");
+
+        let config: Config =
+            toml::from_str("layout = \"dvorak\"\nremap = true").unwrap();
+        assert_eq!(config.layout, "dvorak");
+        assert!(config.remap);
+
+        let config: Config =
+            toml::from_str(r#"logs = ["csv", "json"]"#).unwrap();
+        assert_eq!(config.logs, vec![LogFormat::Csv, LogFormat::Json]);
+
+        assert!(toml::from_str::<Config>("live_stat = false").is_err());
+    }
+}
@@ -0,0 +1,426 @@
+use crate::results::{Keystroke, TestResult};
+use crate::thok::KeyStats;
+use chrono::prelude::*;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{collections::BTreeMap, error::Error, fs, path::Path};
+
+/// each entry moves the schema up by one version. the version a database is
+/// at is kept in sqlite's `user_version`, so only new entries are applied
+const MIGRATIONS: &[&str] = &[
+    // 1: runs along with their keystrokes and per-key aggregates
+    "CREATE TABLE runs (
+        id INTEGER PRIMARY KEY,
+        date TEXT NOT NULL,
+        language TEXT,
+        seed INTEGER,
+        num_words INTEGER NOT NULL,
+        num_secs REAL,
+        pace REAL,
+        strict TEXT,
+        death_mode TEXT,
+        elapsed_secs REAL NOT NULL,
+        wpm REAL NOT NULL,
+        accuracy REAL NOT NULL,
+        std_dev REAL NOT NULL,
+        mistakes INTEGER NOT NULL
+    );
+    CREATE INDEX runs_date ON runs (date);
+    CREATE TABLE keystrokes (
+        run_id INTEGER NOT NULL REFERENCES runs (id) ON DELETE CASCADE,
+        idx INTEGER NOT NULL,
+        expected TEXT NOT NULL,
+        typed TEXT NOT NULL,
+        ms INTEGER NOT NULL,
+        PRIMARY KEY (run_id, idx)
+    );
+    CREATE TABLE key_stats (
+        run_id INTEGER NOT NULL REFERENCES runs (id) ON DELETE CASCADE,
+        key TEXT NOT NULL,
+        presses INTEGER NOT NULL,
+        misses INTEGER NOT NULL,
+        mean_ms REAL,
+        PRIMARY KEY (run_id, key)
+    );
+    CREATE TABLE imports (
+        source TEXT PRIMARY KEY,
+        imported_at TEXT NOT NULL
+    );",
+    // 2: the prompt of each run, for replaying it
+    "ALTER TABLE runs ADD COLUMN prompt TEXT;",
+    // 3: runs imported from other tools may have no standard deviation.
+    // sqlite can't loosen a column, so the table is copied into a new one
+    "CREATE TABLE runs_new (
+        id INTEGER PRIMARY KEY,
+        date TEXT NOT NULL,
+        language TEXT,
+        seed INTEGER,
+        num_words INTEGER NOT NULL,
+        num_secs REAL,
+        pace REAL,
+        strict TEXT,
+        death_mode TEXT,
+        elapsed_secs REAL NOT NULL,
+        wpm REAL NOT NULL,
+        accuracy REAL NOT NULL,
+        std_dev REAL,
+        mistakes INTEGER NOT NULL,
+        prompt TEXT
+    );
+    INSERT INTO runs_new SELECT id, date, language, seed, num_words, num_secs,
+        pace, strict, death_mode, elapsed_secs, wpm, accuracy, std_dev,
+        mistakes, prompt FROM runs;
+    DROP TABLE runs;
+    ALTER TABLE runs_new RENAME TO runs;
+    CREATE INDEX runs_date ON runs (date);",
+];
+
+/// opens the database, creating it and bringing its schema up to date as needed
+pub fn open(path: &Path) -> rusqlite::Result<Connection> {
+    let mut conn = Connection::open(path)?;
+    migrate(&mut conn)?;
+
+    Ok(conn)
+}
+
+fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+    let version: usize =
+        conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    // foreign keys are left unenforced while migrating, so that replacing the
+    // runs table doesn't delete the keystrokes and key stats of every run
+    conn.pragma_update(None, "foreign_keys", false)?;
+    let tx = conn.transaction()?;
+    for (idx, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", idx + 1)?;
+    }
+    tx.commit()?;
+    conn.pragma_update(None, "foreign_keys", true)
+}
+
+pub fn insert_result(
+    conn: &mut Connection,
+    result: &TestResult,
+) -> Result<i64, Box<dyn Error>> {
+    let tx = conn.transaction()?;
+
+    tx.execute(
+        "INSERT INTO runs (date, language, seed, num_words, num_secs, pace,
+            strict, death_mode, elapsed_secs, wpm, accuracy, std_dev, mistakes,
+            prompt)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            result.date.to_rfc3339(),
+            result.language,
+            // sqlite integers are signed, so the seed is stored bit for bit
+            result.seed.map(|s| s as i64),
+            result.num_words,
+            result.num_secs,
+            result.pace,
+            result
+                .strict
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?,
+            result
+                .death_mode
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?,
+            result.elapsed_secs,
+            result.wpm,
+            result.accuracy,
+            result.std_dev,
+            result.mistakes,
+            result.prompt,
+        ],
+    )?;
+    let run_id = tx.last_insert_rowid();
+
+    for (idx, keystroke) in result.keystrokes.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO keystrokes (run_id, idx, expected, typed, ms)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                run_id,
+                idx,
+                keystroke.expected.to_string(),
+                keystroke.typed.to_string(),
+                keystroke.ms,
+            ],
+        )?;
+    }
+
+    for (key, stats) in &result.keys {
+        tx.execute(
+            "INSERT INTO key_stats (run_id, key, presses, misses, mean_ms)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                run_id,
+                key.to_string(),
+                stats.presses,
+                stats.misses,
+                stats.mean_ms,
+            ],
+        )?;
+    }
+
+    tx.commit()?;
+
+    Ok(run_id)
+}
+
+/// every run in the database, oldest first
+pub fn load_results(
+    conn: &Connection,
+) -> Result<Vec<TestResult>, Box<dyn Error>> {
+    let mut runs = conn.prepare(
+        "SELECT id, date, language, seed, num_words, num_secs, pace, strict,
+            death_mode, elapsed_secs, wpm, accuracy, std_dev, mistakes, prompt
+        FROM runs ORDER BY date, id",
+    )?;
+    let mut keystrokes = conn.prepare(
+        "SELECT expected, typed, ms FROM keystrokes
+        WHERE run_id = ?1 ORDER BY idx",
+    )?;
+    let mut key_stats = conn.prepare(
+        "SELECT key, presses, misses, mean_ms FROM key_stats WHERE run_id = ?1",
+    )?;
+
+    let mut results = vec![];
+    let mut rows = runs.query([])?;
+    while let Some(row) = rows.next()? {
+        let run_id: i64 = row.get(0)?;
+        let date: String = row.get(1)?;
+        let strict: Option<String> = row.get(7)?;
+        let death_mode: Option<String> = row.get(8)?;
+
+        let mut keys = BTreeMap::new();
+        for key in key_stats.query_map([run_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                KeyStats {
+                    presses: row.get(1)?,
+                    misses: row.get(2)?,
+                    mean_ms: row.get(3)?,
+                },
+            ))
+        })? {
+            let (key, stats) = key?;
+            if let Some(c) = key.chars().next() {
+                keys.insert(c, stats);
+            }
+        }
+
+        let keystrokes = keystrokes
+            .query_map([run_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get(2)?,
+                ))
+            })?
+            .filter_map(|k| {
+                let (expected, typed, ms) = k.ok()?;
+                Some(Keystroke {
+                    expected: expected.chars().next()?,
+                    typed: typed.chars().next()?,
+                    ms,
+                })
+            })
+            .collect();
+
+        results.push(TestResult {
+            date: DateTime::parse_from_rfc3339(&date)?.with_timezone(&Local),
+            language: row.get(2)?,
+            seed: row.get::<_, Option<i64>>(3)?.map(|s| s as u64),
+            prompt: row.get(14)?,
+            num_words: row.get(4)?,
+            num_secs: row.get(5)?,
+            pace: row.get(6)?,
+            strict: strict.as_deref().map(serde_json::from_str).transpose()?,
+            death_mode: death_mode
+                .as_deref()
+                .map(serde_json::from_str)
+                .transpose()?,
+            elapsed_secs: row.get(9)?,
+            wpm: row.get(10)?,
+            accuracy: row.get(11)?,
+            std_dev: row.get(12)?,
+            mistakes: row.get(13)?,
+            keys,
+            keystrokes,
+        });
+    }
+
+    Ok(results)
+}
+
+/// copies the rows of an existing `log.csv` into the database. each csv log
+/// is only ever imported once, so this is safe to call every time
+pub fn import_csv_once(
+    conn: &mut Connection,
+    csv_path: &Path,
+) -> Result<usize, Box<dyn Error>> {
+    let source = csv_path.to_string_lossy().to_string();
+    let already_imported = conn
+        .query_row("SELECT 1 FROM imports WHERE source = ?1", [&source], |_| {
+            Ok(())
+        })
+        .optional()?
+        .is_some();
+
+    if already_imported {
+        return Ok(0);
+    }
+
+    let mut imported = 0;
+    if csv_path.exists() {
+        for result in fs::read_to_string(csv_path)?
+            .lines()
+            .filter_map(TestResult::from_csv_row)
+        {
+            insert_result(conn, &result)?;
+            imported += 1;
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO imports (source, imported_at) VALUES (?1, ?2)",
+        params![source, Local::now().to_rfc3339()],
+    )?;
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thok::{StrictMode, Thok};
+
+    #[test]
+    fn test_migrations() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        // running them again is a no-op
+        migrate(&mut conn).unwrap();
+
+        let version: usize = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+    }
+
+    #[test]
+    fn test_loosen_std_dev() {
+        let db_path = std::env::temp_dir()
+            .join(format!("thokr-migrate-{}.db", std::process::id()));
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch(MIGRATIONS[0]).unwrap();
+            conn.execute_batch(MIGRATIONS[1]).unwrap();
+            conn.pragma_update(None, "user_version", 2).unwrap();
+            conn.execute_batch(
+                "INSERT INTO runs (id, date, num_words, elapsed_secs, wpm,
+                    accuracy, std_dev, mistakes)
+                VALUES (1, '2022-03-01T12:00:00+00:00', 2, 1, 60, 100, 1.5, 0);
+                INSERT INTO keystrokes (run_id, idx, expected, typed, ms)
+                VALUES (1, 0, 'a', 'a', 0);",
+            )
+            .unwrap();
+        }
+
+        let mut conn = open(&db_path).unwrap();
+        let mut result = TestResult::bare(Local::now());
+        result.std_dev = None;
+        insert_result(&mut conn, &result).unwrap();
+
+        let loaded = load_results(&conn).unwrap();
+        drop(conn);
+        fs::remove_file(&db_path).unwrap();
+        // the old run keeps its keystrokes
+        assert_eq!(loaded[0].std_dev, Some(1.5));
+        assert_eq!(loaded[0].keystrokes.len(), 1);
+        assert_eq!(loaded[1].std_dev, None);
+    }
+
+    #[test]
+    fn test_insert_and_load() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+
+        let mut thok = Thok::new(
+            "ab cd".to_string(),
+            2,
+            None,
+            None,
+            None,
+            Some(StrictMode::Word),
+        );
+        "ab cx".chars().for_each(|c| thok.write(c));
+        thok.seed = Some(u64::MAX);
+        let result = TestResult::from_thok(&thok);
+
+        let run_id = insert_result(&mut conn, &result).unwrap();
+
+        let (seed, strict): (i64, String) = conn
+            .query_row(
+                "SELECT seed, strict FROM runs WHERE id = ?1",
+                [run_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(seed as u64, u64::MAX);
+        assert_eq!(strict, "\"word\"");
+
+        let count = |table: &str| -> usize {
+            conn.query_row(
+                &format!("SELECT COUNT(*) FROM {} WHERE run_id = ?1", table),
+                [run_id],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(count("keystrokes"), 5);
+        assert_eq!(count("key_stats"), result.keys.len());
+
+        let loaded = load_results(&conn).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].seed, Some(u64::MAX));
+        assert_eq!(loaded[0].prompt.as_deref(), Some("ab cd"));
+        assert_eq!(loaded[0].strict, Some(StrictMode::Word));
+        assert_eq!(loaded[0].keystrokes, result.keystrokes);
+        assert_eq!(loaded[0].keys.len(), result.keys.len());
+        assert_eq!(loaded[0].date.timestamp(), result.date.timestamp());
+    }
+
+    #[test]
+    fn test_import_csv_once() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+
+        let csv_path = std::env::temp_dir()
+            .join(format!("thokr-import-{}.csv", std::process::id()));
+        fs::write(
+            &csv_path,
+            "date,num_words,num_secs,elapsed_secs,wpm,accuracy,std_dev\n\
+            Tue Mar  1 12:00:00 2022,15,,9.41,96,100,1.57\n\
+            Wed Mar  2 12:00:00 2022,15,30.00,30.02,88,97,2.10\n",
+        )
+        .unwrap();
+
+        assert_eq!(import_csv_once(&mut conn, &csv_path).unwrap(), 2);
+        assert_eq!(import_csv_once(&mut conn, &csv_path).unwrap(), 0);
+        fs::remove_file(&csv_path).unwrap();
+
+        let (num_secs, wpm): (Option<f64>, f64) = conn
+            .query_row(
+                "SELECT num_secs, wpm FROM runs ORDER BY date DESC",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(num_secs, Some(30.));
+        assert_eq!(wpm, 88.);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::layout::{Finger, Layout};
+use crate::results::Keystroke;
+use crate::util::mean;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Default, Debug, Clone, PartialEq)]
+pub struct FingerStats {
+    pub presses: usize,
+    pub misses: usize,
+    /// mean time since the previous keystroke, in milliseconds
+    pub mean_ms: Option<f64>,
+}
+
+impl FingerStats {
+    pub fn error_rate(&self) -> f64 {
+        self.misses as f64 / self.presses.max(1) as f64 * 100.0
+    }
+}
+
+/// how the work of typing was spread over the fingers and hands
+#[derive(Serialize, Default, Debug, PartialEq)]
+pub struct FingerUsage {
+    pub fingers: BTreeMap<Finger, FingerStats>,
+    /// percentage of consecutive keys within a word that are different keys
+    /// pressed by the same finger, which forces that finger to travel
+    pub same_finger_bigrams: Option<f64>,
+    /// percentage of consecutive keys within a word typed by different
+    /// hands
+    pub hand_alternation: Option<f64>,
+}
+
+impl FingerUsage {
+    /// the finger with the highest mean time per key
+    pub fn slowest(&self) -> Option<(Finger, f64)> {
+        self.fingers
+            .iter()
+            .filter_map(|(finger, stats)| Some((*finger, stats.mean_ms?)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// the finger with the highest share of mistakes, if any were made
+    pub fn least_accurate(&self) -> Option<(Finger, f64)> {
+        self.fingers
+            .iter()
+            .filter(|(_, stats)| stats.misses > 0)
+            .map(|(finger, stats)| (*finger, stats.error_rate()))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+/// works out finger usage over the keystrokes of one or more tests, with the
+/// fingers each key belongs to looked up in `layout`. keys the layout
+/// doesn't have are left out
+pub fn analyze<'a>(
+    layout: &Layout,
+    tests: impl IntoIterator<Item = &'a [Keystroke]>,
+) -> FingerUsage {
+    let mut timings: BTreeMap<Finger, Vec<f64>> = BTreeMap::new();
+    let mut usage = FingerUsage::default();
+    let (mut bigrams, mut same_finger, mut alternating) = (0, 0, 0);
+
+    for keystrokes in tests {
+        for (idx, keystroke) in keystrokes.iter().enumerate() {
+            let Some(finger) = layout.finger(keystroke.expected) else {
+                continue;
+            };
+
+            let stats = usage.fingers.entry(finger).or_default();
+            stats.presses += 1;
+            if keystroke.typed != keystroke.expected {
+                stats.misses += 1;
+            }
+
+            let Some(prev) = idx.checked_sub(1).map(|prev| keystrokes[prev])
+            else {
+                continue;
+            };
+            timings
+                .entry(finger)
+                .or_default()
+                .push(keystroke.ms.saturating_sub(prev.ms) as f64);
+
+            // pairs broken up by a space don't say much about either hand
+            let Some(prev_finger) = layout.finger(prev.expected) else {
+                continue;
+            };
+            let (Some(prev_left), Some(left)) =
+                (prev_finger.is_left(), finger.is_left())
+            else {
+                continue;
+            };
+
+            bigrams += 1;
+            if prev_finger == finger
+                && layout.position(prev.expected)
+                    != layout.position(keystroke.expected)
+            {
+                same_finger += 1;
+            }
+            if prev_left != left {
+                alternating += 1;
+            }
+        }
+    }
+
+    for (finger, times) in timings {
+        if let Some(stats) = usage.fingers.get_mut(&finger) {
+            stats.mean_ms = mean(&times);
+        }
+    }
+    let percentage = |count: usize, total: usize| {
+        (total > 0).then(|| count as f64 / total as f64 * 100.0)
+    };
+    usage.same_finger_bigrams = percentage(same_finger, bigrams);
+    usage.hand_alternation = percentage(alternating, bigrams);
+
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keystrokes(typed: &str, expected: &str) -> Vec<Keystroke> {
+        typed
+            .chars()
+            .zip(expected.chars())
+            .enumerate()
+            .map(|(i, (typed, expected))| Keystroke {
+                expected,
+                typed,
+                ms: i as u64 * 100,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_analyze() {
+        let qwerty = Layout::qwerty();
+        // d, e and d again all fall to the left middle finger before k
+        // switches to the right hand
+        let mut test = keystrokes("dexk", "dedk");
+        test[2].ms = 260;
+        let usage = analyze(&qwerty, [test.as_slice()]);
+
+        let middle = &usage.fingers[&Finger::LeftMiddle];
+        assert_eq!(middle.presses, 3);
+        assert_eq!(middle.misses, 1);
+        assert_eq!(middle.mean_ms, Some(130.0));
+        assert_eq!(usage.fingers[&Finger::RightMiddle].presses, 1);
+        assert_eq!(usage.same_finger_bigrams.map(f64::round), Some(67.0));
+        assert_eq!(usage.hand_alternation.map(f64::round), Some(33.0));
+        assert_eq!(usage.slowest(), Some((Finger::LeftMiddle, 130.0)));
+        let (finger, error_rate) = usage.least_accurate().unwrap();
+        assert_eq!(finger, Finger::LeftMiddle);
+        assert_eq!(error_rate.round(), 33.0);
+
+        // dvorak puts the same keys under other fingers
+        let dvorak = Layout::load("dvorak").unwrap();
+        let usage = analyze(&dvorak, [test.as_slice()]);
+        assert_eq!(usage.fingers[&Finger::RightIndex].presses, 2);
+
+        assert_eq!(analyze(&qwerty, []), FingerUsage::default());
+    }
+}
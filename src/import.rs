@@ -0,0 +1,339 @@
+use crate::results::{self, LogFormat, TestResult};
+use chrono::prelude::*;
+use clap::{ArgEnum, Args};
+use csv::{ReaderBuilder, StringRecord};
+use std::{collections::HashSet, error::Error, fs, path::PathBuf};
+
+/// convert results exported from another typing tool into thokr's logs
+#[derive(Args, Debug, Clone)]
+pub struct ImportArgs {
+    /// the tool the file was exported from
+    #[clap(long, arg_enum)]
+    from: ImportSource,
+
+    /// with `--from csv`, the column holding a field, as `field=column`.
+    /// date, wpm and accuracy are required [fields: date, wpm, accuracy,
+    /// elapsed-secs, num-words, num-secs, std-dev, language]
+    #[clap(long = "map", parse(try_from_str = parse_mapping))]
+    mappings: Vec<(Field, String)>,
+
+    /// with `--from csv`, the strftime format of the date column. without it
+    /// rfc 3339 dates and unix timestamps are understood
+    #[clap(long)]
+    date_format: Option<String>,
+
+    /// the exported results
+    file: PathBuf,
+}
+
+#[derive(Debug, Copy, Clone, ArgEnum)]
+enum ImportSource {
+    /// the csv export from monkeytype's account page
+    Monkeytype,
+    /// any csv file with a header row, described with `--map`
+    Csv,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, ArgEnum)]
+enum Field {
+    Date,
+    Wpm,
+    Accuracy,
+    ElapsedSecs,
+    NumWords,
+    NumSecs,
+    StdDev,
+    Language,
+}
+
+fn parse_mapping(s: &str) -> Result<(Field, String), String> {
+    let (field, column) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected `field=column`, got `{}`", s))?;
+
+    Ok((
+        Field::from_str(field.trim(), true)?,
+        column.trim().to_owned(),
+    ))
+}
+
+/// reads a date as rfc 3339, or as a unix timestamp in seconds or
+/// milliseconds
+fn parse_date(s: &str, format: Option<&str>) -> Option<DateTime<Local>> {
+    if let Some(format) = format {
+        let date =
+            NaiveDateTime::parse_from_str(s, format).ok().or_else(|| {
+                NaiveDate::parse_from_str(s, format)
+                    .ok()?
+                    .and_hms_opt(0, 0, 0)
+            })?;
+        return Local.from_local_datetime(&date).earliest();
+    }
+
+    if let Ok(date) = DateTime::parse_from_rfc3339(s) {
+        return Some(date.with_timezone(&Local));
+    }
+
+    let timestamp = s.parse::<i64>().ok()?;
+    // anything this large would be thousands of years away in seconds
+    let millis = if timestamp > 100_000_000_000 {
+        timestamp
+    } else {
+        timestamp * 1000
+    };
+    Local.timestamp_millis_opt(millis).single()
+}
+
+/// reads a number, ignoring a trailing percent sign
+fn parse_number(s: &str) -> Option<f64> {
+    s.trim().trim_end_matches('%').parse().ok()
+}
+
+struct Row<'a> {
+    headers: &'a StringRecord,
+    record: &'a StringRecord,
+}
+
+impl Row<'_> {
+    fn get(&self, column: &str) -> Option<&str> {
+        let idx = self.headers.iter().position(|h| h.trim() == column)?;
+        self.record
+            .get(idx)
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    }
+
+    fn number(&self, column: &str) -> Option<f64> {
+        self.get(column).and_then(parse_number)
+    }
+}
+
+/// monkeytype records the mode (`time`, `words`, ...) and its setting (`30`,
+/// `50`, ...) in separate columns
+fn from_monkeytype(row: &Row) -> Option<TestResult> {
+    let mode_setting = row.get("mode2").and_then(|m| m.parse::<usize>().ok());
+
+    Some(TestResult {
+        wpm: row.number("wpm")?,
+        accuracy: row.number("acc")?,
+        elapsed_secs: row.number("testDuration").unwrap_or(0.0),
+        num_words: match row.get("mode") {
+            Some("words") => mode_setting.unwrap_or(0),
+            _ => 0,
+        },
+        num_secs: match row.get("mode") {
+            Some("time") => mode_setting.map(|s| s as f64),
+            _ => None,
+        },
+        language: row.get("language").map(str::to_owned),
+        ..TestResult::bare(parse_date(row.get("timestamp")?, None)?)
+    })
+}
+
+fn from_mapped(
+    row: &Row,
+    mappings: &[(Field, String)],
+    date_format: Option<&str>,
+) -> Option<TestResult> {
+    let column = |field: Field| {
+        mappings
+            .iter()
+            .find(|(f, _)| *f == field)
+            .map(|(_, column)| column.as_str())
+    };
+    let number = |field: Field| column(field).and_then(|c| row.number(c));
+
+    Some(TestResult {
+        wpm: number(Field::Wpm)?,
+        accuracy: number(Field::Accuracy)?,
+        elapsed_secs: number(Field::ElapsedSecs).unwrap_or(0.0),
+        num_words: number(Field::NumWords).map_or(0, |n| n as usize),
+        num_secs: number(Field::NumSecs),
+        std_dev: number(Field::StdDev),
+        language: column(Field::Language)
+            .and_then(|c| row.get(c))
+            .map(str::to_owned),
+        ..TestResult::bare(parse_date(
+            row.get(column(Field::Date)?)?,
+            date_format,
+        )?)
+    })
+}
+
+/// parses every row of the export, returning the results along with the
+/// number of rows that couldn't be read
+fn read_results(
+    args: &ImportArgs,
+    contents: &str,
+) -> Result<(Vec<TestResult>, usize), Box<dyn Error>> {
+    if let ImportSource::Csv = args.from {
+        for field in [Field::Date, Field::Wpm, Field::Accuracy] {
+            if !args.mappings.iter().any(|(f, _)| *f == field) {
+                return Err(format!(
+                    "`--map {:?}=<column>` is required with `--from csv`",
+                    field
+                )
+                .to_lowercase()
+                .into());
+            }
+        }
+    }
+
+    // older monkeytype exports separate columns with pipes
+    let delimiter = match contents.lines().next() {
+        Some(header) if header.contains('|') && !header.contains(',') => b'|',
+        _ => b',',
+    };
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(contents.as_bytes());
+    let headers = reader.headers()?.clone();
+
+    let mut results = vec![];
+    let mut unreadable = 0;
+    for record in reader.records() {
+        let record = record?;
+        let row = Row {
+            headers: &headers,
+            record: &record,
+        };
+        let result = match args.from {
+            ImportSource::Monkeytype => from_monkeytype(&row),
+            ImportSource::Csv => {
+                from_mapped(&row, &args.mappings, args.date_format.as_deref())
+            }
+        };
+
+        match result {
+            Some(result) => results.push(result),
+            None => unreadable += 1,
+        }
+    }
+
+    Ok((results, unreadable))
+}
+
+pub fn run(
+    args: &ImportArgs,
+    log_formats: &[LogFormat],
+) -> Result<(), Box<dyn Error>> {
+    let contents = fs::read_to_string(&args.file)?;
+    let (mut results, unreadable) = read_results(args, &contents)?;
+
+    // importing the same export twice shouldn't count those tests twice
+    let logged = results::load_history(log_formats)?
+        .into_iter()
+        .map(|r| r.date.timestamp())
+        .collect::<HashSet<i64>>();
+    let total = results.len();
+    results.retain(|r| !logged.contains(&r.date.timestamp()));
+    results.sort_by_key(|r| r.date);
+
+    results::save(&results, log_formats)?;
+
+    println!(
+        "imported {} results ({} already logged, {} unreadable rows)",
+        results.len(),
+        total - results.len(),
+        unreadable
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[clap(flatten)]
+        args: ImportArgs,
+    }
+
+    #[test]
+    fn test_monkeytype() {
+        let args = TestCli::parse_from([
+            "import",
+            "--from",
+            "monkeytype",
+            "results.csv",
+        ])
+        .args;
+        let export =
+            "_id,isPb,wpm,acc,rawWpm,consistency,charStats,mode,mode2,\
+            quoteLength,restartCount,testDuration,afkDuration,\
+            incompleteTestSeconds,lazyMode,blindMode,bailedOut,tags,funbox,\
+            language,punctuation,numbers,timestamp\n\
+            a1,true,92.4,97.5,95.1,80.2,230;3;1;0,time,30,-1,0,30,0,0,false,\
+            false,false,,none,english,false,false,1646136000000\n\
+            a2,false,80,96,83,78,\"120;2;0;0\",words,25,-1,1,18.7,0,0,false,\
+            false,false,,none,english_1k,false,false,1646222400000\n\
+            a3,false,,96,83,78,,words,25,-1,1,18.7,0,0,false,false,false,,,\
+            english,false,false,1646222400000\n";
+
+        let (results, unreadable) = read_results(&args, export).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(unreadable, 1);
+
+        assert_eq!(results[0].wpm, 92.4);
+        assert_eq!(results[0].num_secs, Some(30.));
+        assert_eq!(results[0].date.timestamp(), 1646136000);
+        assert_eq!(results[0].std_dev, None);
+        assert_eq!(results[1].num_words, 25);
+        assert_eq!(results[1].elapsed_secs, 18.7);
+        assert_eq!(results[1].language.as_deref(), Some("english_1k"));
+    }
+
+    #[test]
+    fn test_mapped_csv() {
+        let args = TestCli::parse_from([
+            "import",
+            "--from",
+            "csv",
+            "--map",
+            "date=When",
+            "--map",
+            "wpm=Speed",
+            "--map",
+            "accuracy = Accuracy",
+            "--map",
+            "num-words=Words",
+            "--date-format",
+            "%d/%m/%Y %H:%M",
+            "results.csv",
+        ])
+        .args;
+        let export = "When,Speed,Accuracy,Words\n\
+            01/03/2022 12:00,71,98%,50\n\
+            not a date,71,98%,50\n";
+
+        let (results, unreadable) = read_results(&args, export).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(unreadable, 1);
+        assert_eq!(results[0].accuracy, 98.);
+        assert_eq!(results[0].num_words, 50);
+        assert_eq!(
+            results[0].date.naive_local(),
+            NaiveDate::from_ymd_opt(2022, 3, 1)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap()
+        );
+
+        let args = TestCli::parse_from([
+            "import",
+            "--from",
+            "csv",
+            "--map",
+            "wpm=Speed",
+            "results.csv",
+        ])
+        .args;
+        assert!(read_results(&args, export).is_err());
+        assert!(parse_mapping("speed").is_err());
+        assert!(parse_mapping("speed=Speed").is_err());
+    }
+}
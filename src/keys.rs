@@ -0,0 +1,471 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+/// something a key can be bound to
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Deserialize,
+    strum_macros::Display,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Menu,
+    Restart,
+    New,
+    Options,
+    WordDelete,
+    Help,
+    /// only while the options or the results are showing
+    Retry,
+    /// only while the options or the results are showing
+    Next,
+    /// only while the results are showing
+    Missed,
+    /// only on the menu and settings screens
+    Up,
+    /// only on the menu and settings screens
+    Down,
+    /// only on the menu and settings screens
+    Select,
+    /// only on the settings screen
+    Decrease,
+    /// only on the settings screen
+    Increase,
+    /// only on the menu and settings screens
+    Back,
+}
+
+/// what's on screen, which decides the actions a key can stand for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Context {
+    /// the menu or the settings
+    Menus,
+    /// a test being typed
+    Typing,
+    /// a test with the options or the results showing
+    Options,
+}
+
+impl Action {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Menu => "back to the menu",
+            Action::Restart => "restart the test",
+            Action::New => "new test",
+            Action::Options => "show or hide the options",
+            Action::WordDelete => "delete the last word",
+            Action::Help => "show or hide this help",
+            Action::Retry => "retry (options and results)",
+            Action::Next => "new test (options and results)",
+            Action::Missed => "practise missed words (results)",
+            Action::Up => "move up (menus)",
+            Action::Down => "move down (menus)",
+            Action::Select => "choose (menus)",
+            Action::Decrease => "lower a setting (settings)",
+            Action::Increase => "raise a setting (settings)",
+            Action::Back => "back, or quit from the menu (menus)",
+        }
+    }
+
+    /// whether the action applies in `context`. the options, results and
+    /// menus take keys that would otherwise be typed or mean something else
+    /// in a test, so their actions only apply there
+    pub fn applies_in(&self, context: Context) -> bool {
+        match self {
+            Action::Quit | Action::Menu | Action::Help => true,
+            Action::Restart
+            | Action::New
+            | Action::Options
+            | Action::WordDelete => context != Context::Menus,
+            Action::Retry | Action::Next | Action::Missed => {
+                context == Context::Options
+            }
+            Action::Up
+            | Action::Down
+            | Action::Select
+            | Action::Decrease
+            | Action::Increase
+            | Action::Back => context == Context::Menus,
+        }
+    }
+}
+
+/// a key together with the modifiers held down with it, written like
+/// `ctrl+h`, `alt+backspace`, `f1` or `r`
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    fn matches(&self, key: &KeyEvent) -> bool {
+        // shift is part of which character a key types
+        let ignored = match key.code {
+            KeyCode::Char(_) => KeyModifiers::SHIFT,
+            _ => KeyModifiers::NONE,
+        };
+
+        self.code == key.code && self.modifiers == key.modifiers - ignored
+    }
+}
+
+impl From<KeyBinding> for KeyEvent {
+    fn from(binding: KeyBinding) -> Self {
+        KeyEvent {
+            code: binding.code,
+            modifiers: binding.modifiers,
+        }
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut parts = s.split('+').collect::<Vec<&str>>();
+        // a lone `+` is the plus key rather than a separator
+        let key = match parts.pop() {
+            Some("") if parts.last() == Some(&"") => {
+                parts.pop();
+                "+"
+            }
+            Some("") | None => return Err(format!("no key in `{}`", s)),
+            Some(key) => key,
+        };
+
+        for modifier in parts {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => {
+                    return Err(format!(
+                        "unknown modifier `{}` in `{}`",
+                        modifier, s
+                    ))
+                }
+            };
+        }
+
+        let code = match key.to_lowercase().as_str() {
+            "esc" => KeyCode::Esc,
+            "enter" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "space" => KeyCode::Char(' '),
+            f if f.len() > 1 && f.starts_with('f') => KeyCode::F(
+                f[1..]
+                    .parse()
+                    .map_err(|_| format!("unknown key `{}`", key))?,
+            ),
+            _ => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(format!("unknown key `{}`", key)),
+                }
+            }
+        };
+
+        // shift only changes which character a key types, so it's never
+        // held along with one
+        if matches!(code, KeyCode::Char(_))
+            && modifiers.contains(KeyModifiers::SHIFT)
+        {
+            return Err(format!(
+                "shift can't be held with `{}` in `{}`, bind the character \
+                it types instead",
+                key, s
+            ));
+        }
+
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl+"),
+            (KeyModifiers::ALT, "alt+"),
+            (KeyModifiers::SHIFT, "shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Esc => f.write_str("esc"),
+            KeyCode::Enter => f.write_str("enter"),
+            KeyCode::Tab => f.write_str("tab"),
+            KeyCode::Backspace => f.write_str("backspace"),
+            KeyCode::Delete => f.write_str("delete"),
+            KeyCode::Left => f.write_str("←"),
+            KeyCode::Right => f.write_str("→"),
+            KeyCode::Up => f.write_str("↑"),
+            KeyCode::Down => f.write_str("↓"),
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            _ => f.write_str("?"),
+        }
+    }
+}
+
+/// the keys bound to each action. actions left out of the config keep their
+/// default keys
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "BTreeMap<Action, Vec<KeyBinding>>")]
+pub struct Keymap(BTreeMap<Action, Vec<KeyBinding>>);
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bind = |keys: &[&str]| {
+            keys.iter()
+                .map(|key| key.parse().expect("default keys are valid"))
+                .collect()
+        };
+
+        Self(BTreeMap::from([
+            (Action::Quit, bind(&["ctrl+c"])),
+            (Action::Menu, bind(&["esc"])),
+            (Action::Restart, bind(&["ctrl+r", "left"])),
+            (Action::New, bind(&["right"])),
+            (Action::Options, bind(&["tab"])),
+            // many terminals send ctrl+h for ctrl+backspace
+            (
+                Action::WordDelete,
+                bind(&["ctrl+h", "ctrl+backspace", "alt+backspace"]),
+            ),
+            (Action::Help, bind(&["f1"])),
+            (Action::Retry, bind(&["r"])),
+            (Action::Next, bind(&["n"])),
+            (Action::Missed, bind(&["m"])),
+            (Action::Up, bind(&["up"])),
+            (Action::Down, bind(&["down"])),
+            (Action::Select, bind(&["enter"])),
+            (Action::Decrease, bind(&["left"])),
+            (Action::Increase, bind(&["right"])),
+            (Action::Back, bind(&["q"])),
+        ]))
+    }
+}
+
+impl TryFrom<BTreeMap<Action, Vec<KeyBinding>>> for Keymap {
+    type Error = String;
+
+    /// the default keys with `bindings` in place of those of the actions they
+    /// name. a key can't be typed and also stand for an action while typing,
+    /// and can't stand for two actions on the same screen
+    fn try_from(
+        bindings: BTreeMap<Action, Vec<KeyBinding>>,
+    ) -> Result<Self, Self::Error> {
+        let mut keymap = Self::default();
+        keymap.0.extend(bindings);
+
+        for (action, keys) in &keymap.0 {
+            let typed = keys.iter().find(|key| {
+                matches!(key.code, KeyCode::Char(_))
+                    && key.modifiers == KeyModifiers::NONE
+            });
+            if let (Some(key), true) =
+                (typed, action.applies_in(Context::Typing))
+            {
+                return Err(format!(
+                    "`{}` can't be bound to `{}`, as it's typed in a test",
+                    key, action
+                ));
+            }
+        }
+
+        for context in [Context::Menus, Context::Typing, Context::Options] {
+            let bound = keymap
+                .0
+                .iter()
+                .filter(|(action, _)| action.applies_in(context))
+                .flat_map(|(action, keys)| {
+                    keys.iter().map(move |k| (action, k))
+                })
+                .collect::<Vec<(&Action, &KeyBinding)>>();
+            for (idx, (action, key)) in bound.iter().enumerate() {
+                if let Some((other, _)) = bound[idx + 1..]
+                    .iter()
+                    .find(|(other, k)| k == key && other != action)
+                {
+                    return Err(format!(
+                        "`{}` is bound to both `{}` and `{}`",
+                        key, action, other
+                    ));
+                }
+            }
+        }
+
+        Ok(keymap)
+    }
+}
+
+impl Keymap {
+    /// the action bound to `key`, out of those that apply in `context`
+    pub fn action(&self, key: &KeyEvent, context: Context) -> Option<Action> {
+        self.0
+            .iter()
+            .filter(|(action, _)| action.applies_in(context))
+            .find(|(_, keys)| keys.iter().any(|k| k.matches(key)))
+            .map(|(action, _)| *action)
+    }
+
+    /// the first key bound to `action`, for legends
+    pub fn key(&self, action: Action) -> String {
+        self.0
+            .get(&action)
+            .and_then(|keys| keys.first())
+            .map_or(String::from("unbound"), |key| key.to_string())
+    }
+
+    /// every action along with all of the keys bound to it
+    pub fn bindings(&self) -> impl Iterator<Item = (Action, String)> + '_ {
+        self.0.iter().map(|(action, keys)| {
+            (
+                *action,
+                keys.iter()
+                    .map(|key| key.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent { code, modifiers }
+    }
+
+    #[test]
+    fn test_parse() {
+        let binding: KeyBinding = "ctrl+alt+backspace".parse().unwrap();
+        assert!(binding.matches(&key(
+            KeyCode::Backspace,
+            KeyModifiers::CONTROL | KeyModifiers::ALT
+        )));
+        assert_eq!(binding.to_string(), "ctrl+alt+backspace");
+
+        assert_eq!("f12".parse::<KeyBinding>().unwrap().code, KeyCode::F(12));
+        assert_eq!(
+            "ctrl++".parse::<KeyBinding>().unwrap().code,
+            KeyCode::Char('+')
+        );
+        assert_eq!("+".parse::<KeyBinding>().unwrap().code, KeyCode::Char('+'));
+        assert_eq!(
+            "ctrl+".parse::<KeyBinding>(),
+            Err(String::from("no key in `ctrl+`"))
+        );
+        assert!("hyper+x".parse::<KeyBinding>().is_err());
+        assert_eq!(
+            "shift+x".parse::<KeyBinding>(),
+            Err(String::from(
+                "shift can't be held with `x` in `shift+x`, bind the \
+                character it types instead"
+            ))
+        );
+        assert!("ctrl+X".parse::<KeyBinding>().unwrap().matches(&key(
+            KeyCode::Char('X'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT
+        )));
+        assert!("shift+tab".parse::<KeyBinding>().is_ok());
+        assert!("nope".parse::<KeyBinding>().is_err());
+    }
+
+    #[test]
+    fn test_keymap() {
+        let keymap = Keymap::default();
+        let none = KeyModifiers::NONE;
+        let typing = Context::Typing;
+
+        assert_eq!(
+            keymap.action(
+                &key(KeyCode::Char('h'), KeyModifiers::CONTROL),
+                typing
+            ),
+            Some(Action::WordDelete)
+        );
+        assert_eq!(
+            keymap.action(&key(KeyCode::Backspace, KeyModifiers::ALT), typing),
+            Some(Action::WordDelete)
+        );
+        assert_eq!(keymap.action(&key(KeyCode::Char('r'), none), typing), None);
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('r'), none), Context::Options),
+            Some(Action::Retry)
+        );
+        // the same key means something else in the menus
+        assert_eq!(
+            keymap.action(&key(KeyCode::Left, none), typing),
+            Some(Action::Restart)
+        );
+        assert_eq!(
+            keymap.action(&key(KeyCode::Left, none), Context::Menus),
+            Some(Action::Decrease)
+        );
+        assert_eq!(keymap.action(&key(KeyCode::Char('q'), none), typing), None);
+
+        let keymap: Keymap = toml::from_str(r#"restart = ["ctrl+r"]"#).unwrap();
+        assert_eq!(keymap.key(Action::Restart), "ctrl+r");
+        assert_eq!(keymap.action(&key(KeyCode::Left, none), typing), None);
+        assert_eq!(keymap.key(Action::New), "→");
+        assert!(toml::from_str::<Keymap>(r#"restart = ["hyper+r"]"#).is_err());
+    }
+
+    #[test]
+    fn test_keymap_conflicts() {
+        let err = |toml: &str| toml::from_str::<Keymap>(toml).unwrap_err();
+
+        // a letter bound while typing could never be typed
+        assert!(err(r#"restart = ["r"]"#).to_string().contains(
+            "`r` can't be bound to `restart`, as it's typed in a test"
+        ));
+        assert!(err(r#"help = ["space"]"#).to_string().contains("`space`"));
+        // but only the options, results and menus take retry
+        assert!(toml::from_str::<Keymap>(r#"retry = ["x"]"#).is_ok());
+
+        // two actions can't share a key on the same screen
+        assert!(err(r#"options = ["esc"]"#)
+            .to_string()
+            .contains("`esc` is bound to both `menu` and `options`"));
+        assert!(err(r#"next = ["r"]"#)
+            .to_string()
+            .contains("`r` is bound to both `retry` and `next`"));
+        // while left restarts a test and lowers a setting
+        assert!(toml::from_str::<Keymap>(r#"decrease = ["left", "h"]"#).is_ok());
+    }
+}
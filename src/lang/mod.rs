@@ -4,7 +4,8 @@ use serde::Deserialize;
 use serde_json::from_str;
 
 use include_dir::{include_dir, Dir};
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use std::error::Error;
 
 static LANG_DIR: Dir = include_dir!("src/lang");
@@ -45,14 +46,94 @@ impl Language {
         (vec, word_count)
     }
 
-    pub fn get_random(&self, num: usize) -> Vec<String> {
-        let mut rng = &mut rand::thread_rng();
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// picks `num` words typed with nothing but `keys`, every other one
+    /// containing `focus`. words can come up more than once since early
+    /// lessons have very few to choose from
+    pub fn get_using(
+        &self,
+        keys: &[char],
+        focus: char,
+        num: usize,
+        seed: u64,
+    ) -> Vec<String> {
+        let rng = &mut ChaCha8Rng::seed_from_u64(seed);
+        let usable = self
+            .words
+            .iter()
+            .filter(|word| word.chars().all(|c| keys.contains(&c)))
+            .collect::<Vec<&String>>();
+        let focused = usable
+            .iter()
+            .copied()
+            .filter(|word| word.contains(focus))
+            .collect::<Vec<&String>>();
+
+        if usable.is_empty() {
+            return vec![focus.to_string(); num];
+        }
+
+        (0..num)
+            .filter_map(|i| match i % 2 {
+                0 if !focused.is_empty() => focused.choose(rng),
+                _ => usable.choose(rng),
+            })
+            .map(|word| word.to_string())
+            .collect()
+    }
+
+    /// picks `num` words, always the same ones for a given seed. the rng is
+    /// chacha rather than `StdRng`, whose output may change between rand
+    /// versions and platforms, so logged seeds keep repeating their tests
+    pub fn get_random(&self, num: usize, seed: u64) -> Vec<String> {
+        let mut rng = &mut ChaCha8Rng::seed_from_u64(seed);
 
         self.words.choose_multiple(&mut rng, num).cloned().collect()
     }
+
+    /// picks `num` words that each contain one of `ngrams`, taking turns
+    /// between the ngrams so every one of them gets practised. ngrams no word
+    /// contains are skipped, and if none are left it's the same as `get_random`
+    pub fn get_containing(
+        &self,
+        ngrams: &[String],
+        num: usize,
+        seed: u64,
+    ) -> Vec<String> {
+        let rng = &mut ChaCha8Rng::seed_from_u64(seed);
+        let candidates = ngrams
+            .iter()
+            .map(|ngram| {
+                self.words
+                    .iter()
+                    .filter(|word| word.to_lowercase().contains(ngram.as_str()))
+                    .collect::<Vec<&String>>()
+            })
+            .filter(|words| !words.is_empty())
+            .collect::<Vec<Vec<&String>>>();
+
+        if candidates.is_empty() {
+            return self.get_random(num, seed);
+        }
+
+        candidates
+            .iter()
+            .cycle()
+            .take(num)
+            .filter_map(|words| words.choose(rng).map(|word| word.to_string()))
+            .collect::<Vec<String>>()
+            .choose_multiple(rng, num)
+            .cloned()
+            .collect()
+    }
 }
 
-fn read_language_from_file(file_name: String) -> Result<Language, Box<dyn Error>> {
+fn read_language_from_file(
+    file_name: String,
+) -> Result<Language, Box<dyn Error>> {
     let file = LANG_DIR
         .get_file(file_name)
         .expect("Language file not found");
@@ -61,7 +142,51 @@ fn read_language_from_file(file_name: String) -> Result<Language, Box<dyn Error>
         .contents_utf8()
         .expect("Unable to interpret file as a string");
 
-    let lang = from_str(file_as_str).expect("Unable to deserialize language json");
+    let lang =
+        from_str(file_as_str).expect("Unable to deserialize language json");
 
     Ok(lang)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_containing() {
+        let language = Language::new("english".to_string());
+        let ngrams =
+            vec!["th".to_string(), "zzz".to_string(), "ou".to_string()];
+        let words = language.get_containing(&ngrams, 10, 1);
+
+        assert_eq!(words.len(), 10);
+        assert!(words.iter().filter(|w| w.contains("th")).count() >= 5);
+        assert!(words.iter().all(|w| w.contains("th") || w.contains("ou")));
+        assert_eq!(words, language.get_containing(&ngrams, 10, 1));
+
+        assert_eq!(
+            language.get_containing(&[], 3, 1),
+            language.get_random(3, 1)
+        );
+    }
+
+    #[test]
+    fn test_get_random() {
+        let language = Language::new("english".to_string());
+        // logged seeds have to pick the same words on every version
+        assert_eq!(language.get_random(3, 42), vec!["other", "general", "one"]);
+    }
+
+    #[test]
+    fn test_get_using() {
+        let language = Language::new("english".to_string());
+        let keys = "asdfghjkle".chars().collect::<Vec<char>>();
+        let words = language.get_using(&keys, 'e', 10, 1);
+
+        assert_eq!(words.len(), 10);
+        assert!(words.iter().all(|w| w.chars().all(|c| keys.contains(&c))));
+        assert!(words.iter().step_by(2).all(|w| w.contains('e')));
+
+        assert_eq!(language.get_using(&['q'], 'q', 2, 1), vec!["q", "q"]);
+    }
+}
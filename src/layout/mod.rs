@@ -0,0 +1,224 @@
+use include_dir::{include_dir, Dir};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, error::Error, fs, path::Path};
+
+static LAYOUT_DIR: Dir = include_dir!("src/layout");
+
+/// the finger that presses a key under touch typing
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    strum_macros::Display,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Finger {
+    LeftPinky,
+    LeftRing,
+    LeftMiddle,
+    LeftIndex,
+    RightIndex,
+    RightMiddle,
+    RightRing,
+    RightPinky,
+    Thumb,
+}
+
+impl Finger {
+    /// fingers stay in their column on every row, with the index fingers
+    /// also covering the two columns in the middle
+    fn for_column(col: usize) -> Self {
+        match col {
+            0 => Finger::LeftPinky,
+            1 => Finger::LeftRing,
+            2 => Finger::LeftMiddle,
+            3 | 4 => Finger::LeftIndex,
+            5 | 6 => Finger::RightIndex,
+            7 => Finger::RightMiddle,
+            8 => Finger::RightRing,
+            _ => Finger::RightPinky,
+        }
+    }
+
+    /// whether the finger is on the left hand, or `None` for the thumbs
+    /// which can hit the space bar from either side
+    pub fn is_left(&self) -> Option<bool> {
+        match self {
+            Finger::Thumb => None,
+            finger => Some(*finger <= Finger::LeftIndex),
+        }
+    }
+}
+
+/// a keyboard layout, described by the characters on each physical key of the
+/// three letter rows from top to bottom. every layout shares the same key
+/// positions so the same column of the same row is the same physical key
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Layout {
+    name: String,
+    rows: Vec<String>,
+    shifted_rows: Vec<String>,
+}
+
+impl Layout {
+    /// loads one of the bundled layouts by name, or a custom layout from a
+    /// json file with the same shape as the bundled ones
+    pub fn load(name_or_path: &str) -> Result<Self, Box<dyn Error>> {
+        let bundled = LAYOUT_DIR.get_file(format!("{}.json", name_or_path));
+        let layout: Self = match bundled {
+            Some(file) => serde_json::from_str(
+                file.contents_utf8()
+                    .expect("Unable to interpret file as a string"),
+            )?,
+            None if Path::new(name_or_path).is_file() => {
+                serde_json::from_str(&fs::read_to_string(name_or_path)?)?
+            }
+            None => {
+                return Err(format!(
+                    "unknown layout `{}`, expected qwerty, dvorak, colemak, \
+                     workman or a path to a json layout",
+                    name_or_path
+                )
+                .into())
+            }
+        };
+
+        let same_shape = layout.rows.len() == layout.shifted_rows.len()
+            && layout
+                .rows
+                .iter()
+                .zip(&layout.shifted_rows)
+                .all(|(r, s)| r.chars().count() == s.chars().count());
+        if !same_shape {
+            return Err(format!(
+                "layout `{}` needs a shifted key for every key in its rows",
+                layout.name
+            )
+            .into());
+        }
+
+        if !layout
+            .rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .any(char::is_alphabetic)
+        {
+            return Err(
+                format!("layout `{}` has no letters", layout.name).into()
+            );
+        }
+
+        Ok(layout)
+    }
+
+    pub fn qwerty() -> Self {
+        Self::load("qwerty").unwrap()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// the letter rows from top to bottom, as typed without shift
+    pub fn rows(&self) -> &[String] {
+        &self.rows
+    }
+
+    /// row and column of the key that types `c`, with or without shift
+    pub fn position(&self, c: char) -> Option<(usize, usize)> {
+        self.keys()
+            .find(|(_, key)| *key == c)
+            .map(|((_, row, col), _)| (row, col))
+    }
+
+    pub fn finger(&self, c: char) -> Option<Finger> {
+        match c {
+            ' ' => Some(Finger::Thumb),
+            _ => self.position(c).map(|(_, col)| Finger::for_column(col)),
+        }
+    }
+
+    /// maps what the os produces for each physical key under `self` to what
+    /// the same key produces under `target`, so a qwerty system can be used
+    /// to practise another layout. keys `target` doesn't have are left alone
+    pub fn keymap_to(&self, target: &Layout) -> HashMap<char, char> {
+        let target_keys: HashMap<_, _> = target.keys().collect();
+
+        self.keys()
+            .filter_map(|(pos, from)| Some((from, *target_keys.get(&pos)?)))
+            .collect()
+    }
+
+    /// every key with its (shifted, row, column) position
+    fn keys(&self) -> impl Iterator<Item = ((bool, usize, usize), char)> + '_ {
+        let unshifted = self.rows.iter().map(|row| (false, row));
+        let shifted = self.shifted_rows.iter().map(|row| (true, row));
+
+        unshifted.chain(shifted).enumerate().flat_map(
+            move |(i, (shift, row))| {
+                let row_idx = i % self.rows.len();
+                row.chars()
+                    .enumerate()
+                    .map(move |(col, c)| ((shift, row_idx, col), c))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keymap() {
+        let qwerty = Layout::qwerty();
+        let dvorak = Layout::load("dvorak").unwrap();
+        let keymap = qwerty.keymap_to(&dvorak);
+
+        assert_eq!(keymap[&'d'], 'e');
+        assert_eq!(keymap[&'s'], 'o');
+        assert_eq!(keymap[&'q'], '\'');
+        assert_eq!(keymap[&'D'], 'E');
+        assert_eq!(keymap[&'z'], ';');
+        assert!(!keymap.contains_key(&' '));
+
+        let colemak = Layout::load("colemak").unwrap();
+        assert_eq!(qwerty.keymap_to(&colemak)[&'k'], 'e');
+        assert_eq!(qwerty.keymap_to(&qwerty)[&'k'], 'k');
+
+        assert!(Layout::load("azerty").is_err());
+
+        let path = std::env::temp_dir()
+            .join(format!("thokr-layout-{}.json", std::process::id()));
+        fs::write(
+            &path,
+            r#"{"name": "digits", "rows": ["123"], "shifted_rows": ["!@#"]}"#,
+        )
+        .unwrap();
+        let err = Layout::load(path.to_str().unwrap()).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(err.to_string(), "layout `digits` has no letters");
+    }
+
+    #[test]
+    fn test_finger() {
+        let qwerty = Layout::qwerty();
+        let dvorak = Layout::load("dvorak").unwrap();
+
+        assert_eq!(qwerty.position('k'), Some((1, 7)));
+        assert_eq!(qwerty.position('K'), Some((1, 7)));
+        assert_eq!(qwerty.finger('k'), Some(Finger::RightMiddle));
+        assert_eq!(dvorak.finger('k'), Some(Finger::LeftIndex));
+        assert_eq!(qwerty.finger('g'), Some(Finger::LeftIndex));
+        assert_eq!(qwerty.finger('\''), Some(Finger::RightPinky));
+        assert_eq!(qwerty.finger(' '), Some(Finger::Thumb));
+        assert_eq!(qwerty.finger('1'), None);
+    }
+}
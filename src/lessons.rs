@@ -0,0 +1,195 @@
+use crate::lang::Language;
+use crate::layout::Layout;
+use crate::results;
+use crate::thok::KeyStats;
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fs,
+};
+
+/// how many of the most common letters the first lesson of a layout without
+/// letters on its home row starts with
+const FIRST_KEYS_WITHOUT_HOME_ROW: usize = 6;
+
+/// a lesson of the curriculum: the keys unlocked so far and what it takes to
+/// unlock the next one
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lesson {
+    /// unlocked keys in the order they were unlocked. the last one is the
+    /// key in focus, which has to reach the targets before another unlocks
+    pub keys: Vec<char>,
+    pub next: Option<char>,
+    pub target_wpm: f64,
+    pub target_accuracy: f64,
+    /// wpm and accuracy on the focus key in the finished test
+    pub focus_result: Option<(f64, f64)>,
+}
+
+impl Lesson {
+    /// the lesson for however far the curriculum of `layout` has been worked
+    /// through, starting from the home row, or from the most common letters
+    /// when the home row has none
+    pub fn new(
+        layout: &Layout,
+        language: &Language,
+        unlocked: usize,
+        target_wpm: f64,
+        target_accuracy: f64,
+    ) -> Self {
+        let order = curriculum(layout, language);
+        let first = match home_row(layout).len() {
+            0 => FIRST_KEYS_WITHOUT_HOME_ROW.min(order.len()),
+            home => home,
+        };
+        let unlocked = unlocked.clamp(first, order.len());
+
+        Self {
+            keys: order[..unlocked].to_vec(),
+            next: order.get(unlocked).copied(),
+            target_wpm,
+            target_accuracy,
+            focus_result: None,
+        }
+    }
+
+    pub fn focus(&self) -> char {
+        *self.keys.last().expect("layouts have letters")
+    }
+
+    /// records how the focus key went in a finished test and whether that was
+    /// enough to unlock the next key
+    pub fn assess(&mut self, key_stats: &BTreeMap<char, KeyStats>) -> bool {
+        let Some(stats) = key_stats.get(&self.focus()) else {
+            return false;
+        };
+        let Some(mean_ms) = stats.mean_ms else {
+            return false;
+        };
+
+        // a word is five keys, so this is the wpm typing only this key
+        let wpm = 12000.0 / mean_ms;
+        let accuracy = (stats.presses - stats.misses) as f64
+            / stats.presses as f64
+            * 100.0;
+        self.focus_result = Some((wpm, accuracy));
+
+        self.passed()
+    }
+
+    /// whether the finished test unlocked the next key
+    pub fn passed(&self) -> bool {
+        self.next.is_some()
+            && self.focus_result.is_some_and(|(wpm, accuracy)| {
+                wpm >= self.target_wpm && accuracy >= self.target_accuracy
+            })
+    }
+}
+
+/// letters of the layout's home row
+fn home_row(layout: &Layout) -> Vec<char> {
+    layout
+        .rows()
+        .get(1)
+        .map(|row| row.chars().filter(|c| c.is_alphabetic()).collect())
+        .unwrap_or_default()
+}
+
+/// every letter of the layout in the order they're taught: the home row,
+/// then the rest from the most to the least common in `language`
+fn curriculum(layout: &Layout, language: &Language) -> Vec<char> {
+    let mut frequency: HashMap<char, usize> = HashMap::new();
+    for c in language.words().iter().flat_map(|word| word.chars()) {
+        *frequency.entry(c).or_default() += 1;
+    }
+
+    let home = home_row(layout);
+    let mut rest = layout
+        .rows()
+        .iter()
+        .flat_map(|row| row.chars())
+        .filter(|c| c.is_alphabetic() && !home.contains(c))
+        .collect::<Vec<char>>();
+    rest.sort_by_key(|c| {
+        std::cmp::Reverse(frequency.get(c).copied().unwrap_or(0))
+    });
+
+    home.into_iter().chain(rest).collect()
+}
+
+/// how many keys have been unlocked on each layout, kept in `lessons.json`
+/// next to the logs
+pub fn load_progress() -> Result<BTreeMap<String, usize>, Box<dyn Error>> {
+    let Some(path) = results::log_dir().map(|dir| dir.join("lessons.json"))
+    else {
+        return Ok(BTreeMap::new());
+    };
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+pub fn save_progress(
+    progress: &BTreeMap<String, usize>,
+) -> Result<(), Box<dyn Error>> {
+    let Some(dir) = results::log_dir() else {
+        return Ok(());
+    };
+    fs::create_dir_all(&dir)?;
+    fs::write(
+        dir.join("lessons.json"),
+        serde_json::to_string_pretty(progress)?,
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lesson() {
+        let qwerty = Layout::qwerty();
+        let language = Language::new("english".to_string());
+
+        let mut lesson = Lesson::new(&qwerty, &language, 0, 30.0, 90.0);
+        assert_eq!(lesson.keys, "asdfghjkl".chars().collect::<Vec<char>>());
+        assert_eq!(lesson.focus(), 'l');
+        // e is the most common letter outside of the home row
+        assert_eq!(lesson.next, Some('e'));
+
+        let mut key_stats = BTreeMap::new();
+        key_stats.insert(
+            'l',
+            KeyStats {
+                presses: 10,
+                misses: 2,
+                mean_ms: Some(200.0),
+            },
+        );
+        assert!(!lesson.assess(&key_stats));
+        assert_eq!(lesson.focus_result, Some((60.0, 80.0)));
+
+        key_stats.get_mut(&'l').unwrap().misses = 1;
+        assert!(lesson.assess(&key_stats));
+
+        let lesson = Lesson::new(&qwerty, &language, 10, 30.0, 90.0);
+        assert_eq!(lesson.focus(), 'e');
+        let lesson = Lesson::new(&qwerty, &language, 100, 30.0, 90.0);
+        assert_eq!(lesson.keys.len(), 26);
+        assert_eq!(lesson.next, None);
+
+        // a single row has no home row to start from
+        let one_row: Layout = serde_json::from_str(
+            r#"{"name": "one", "rows": ["qwerty"], "shifted_rows": ["QWERTY"]}"#,
+        )
+        .unwrap();
+        let lesson = Lesson::new(&one_row, &language, 0, 30.0, 90.0);
+        assert_eq!(lesson.keys.len(), FIRST_KEYS_WITHOUT_HOME_ROW);
+        assert_eq!(lesson.keys[0], 'e');
+        assert_eq!(lesson.focus(), 'q');
+    }
+}
@@ -1,12 +1,40 @@
+mod config;
+mod db;
+mod fingers;
+mod import;
+mod keys;
 mod lang;
+mod layout;
+mod lessons;
+mod menu;
+mod ngrams;
+mod results;
+mod review;
+mod simulate;
+mod stats;
 mod thok;
 mod ui;
 mod util;
 
-use crate::{lang::Language, thok::Thok};
-use clap::{ArgEnum, ErrorKind, IntoApp, Parser};
+use crate::{
+    config::Config,
+    import::ImportArgs,
+    keys::{Action, Context},
+    lang::Language,
+    layout::Layout,
+    lessons::Lesson,
+    menu::{MenuItem, Screen, MENU_ITEMS, SETTINGS},
+    results::{PrintFormat, TestResult},
+    simulate::SimulateArgs,
+    stats::StatsArgs,
+    thok::{CaretStyle, DeathRules, StrictMode, Thok},
+    ui::{Help, Menu},
+};
+use chrono::Local;
+use clap::{ArgEnum, ErrorKind, IntoApp, Parser, Subcommand};
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    cursor::{CursorShape, SetCursorShape},
+    event::{self, Event, KeyCode, KeyEvent},
     execute,
     terminal::{
         disable_raw_mode, enable_raw_mode, EnterAlternateScreen,
@@ -19,14 +47,18 @@ use ratatui::{
     Frame, Terminal,
 };
 use std::{
+    collections::HashMap,
     error::Error,
-    io::{self, stdin},
+    fs::OpenOptions,
+    io::{self, stdin, Write},
     sync::mpsc,
     thread,
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 
 const TICK_RATE_MS: u64 = 100;
+/// how long the countdown before a test lasts
+const COUNTDOWN: Duration = Duration::from_secs(3);
 
 /// sleek typing tui with visualized results and historical logging
 #[derive(Parser, Debug, Clone)]
@@ -59,15 +91,151 @@ pub struct Cli {
     /// is death mode enabled
     #[clap(short = 'd', long = "death-mode")]
     death_mode: bool,
+
+    /// death mode: number of mistakes allowed before the run ends
+    #[clap(long, parse(try_from_str = parse_lives))]
+    lives: Option<usize>,
+
+    /// death mode: end the run when accuracy drops below this percentage
+    #[clap(long)]
+    min_accuracy: Option<f64>,
+
+    /// death mode: end the run when the wpm over the last few seconds drops
+    /// below this
+    #[clap(long)]
+    min_wpm: Option<f64>,
+
+    /// hold the caret back on mistakes: `letter` waits for the correct key,
+    /// `word` refuses the space until the word is fixed
+    #[clap(long, arg_enum)]
+    strict: Option<StrictMode>,
+
+    /// seed for choosing the words of the first test, to repeat a logged test
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// hide the live wpm, accuracy and progress line while typing
+    #[clap(long = "no-live-stats")]
+    no_live_stats: bool,
+
+    /// practise a weak spot from your logged history instead of random words:
+    /// `ngrams` picks words containing your slowest bigrams and trigrams
+    #[clap(long, arg_enum)]
+    drill: Option<Drill>,
+
+    /// work through lessons that start on the home row and unlock a key at a
+    /// time as you reach the target speed and accuracy on the newest one
+    #[clap(long, conflicts_with = "drill")]
+    lesson: bool,
+
+    /// practise words due for review, scheduled by how well you typed them
+    /// before, mixed with words you haven't practised yet
+    #[clap(long, conflicts_with_all = &["drill", "lesson"])]
+    review: bool,
+
+    /// keyboard layout being practised: qwerty, dvorak, colemak, workman or a
+    /// path to a json layout
+    #[clap(long)]
+    layout: Option<String>,
+
+    /// translate keys typed on a qwerty system into --layout, to practise a
+    /// layout without switching the os over to it
+    #[clap(long)]
+    remap: bool,
+
+    /// draw the keyboard under the prompt, highlighting the next key
+    #[clap(long)]
+    keyboard: bool,
+
+    /// count down from 3 before each test, starting the timer at the end of
+    /// the countdown rather than on the first key
+    #[clap(long)]
+    countdown: bool,
+
+    /// restart the test as soon as the first word is mistyped
+    #[clap(long)]
+    restart_on_typo: bool,
+
+    /// how the caret is drawn. `bar` uses the terminal's own cursor
+    #[clap(long, arg_enum)]
+    caret: Option<CaretStyle>,
+
+    /// glide the caret between characters instead of jumping
+    #[clap(long)]
+    smooth_caret: bool,
+
+    /// mark mistakes with more than colour, leave out the skull art and
+    /// print a plain text summary of each test on exit
+    #[clap(long)]
+    accessible: bool,
+
+    /// print the results of the last test on exit, as `text` or `json`
+    #[clap(
+        long,
+        arg_enum,
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    print_results: Option<PrintFormat>,
+
+    /// place the terminal's own cursor on the caret, for screen readers and
+    /// terminals that draw it better
+    #[clap(long)]
+    terminal_cursor: bool,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
 }
 
-#[derive(Debug, Copy, Clone, ArgEnum, strum_macros::Display)]
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    Stats(StatsArgs),
+    Import(ImportArgs),
+    Simulate(SimulateArgs),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, ArgEnum, strum_macros::Display)]
 enum SupportedLanguage {
     English,
     English1k,
     English10k,
 }
 
+#[derive(Debug, Copy, Clone, ArgEnum)]
+enum Drill {
+    Ngrams,
+}
+
+impl Cli {
+    /// any death mode rule enables death mode. plain `-d` ends the run on the
+    /// first mistake
+    fn death_rules(&self) -> Option<DeathRules> {
+        if !self.death_mode
+            && self.lives.is_none()
+            && self.min_accuracy.is_none()
+            && self.min_wpm.is_none()
+        {
+            return None;
+        }
+
+        Some(DeathRules {
+            lives: self.lives.or(self.death_mode.then_some(1)),
+            min_accuracy: self.min_accuracy,
+            min_wpm: self.min_wpm,
+        })
+    }
+}
+
+/// at least one life, since a run without any would end before it started
+fn parse_lives(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>().map_err(|e| e.to_string())? {
+        0 => Err(String::from("must be at least 1")),
+        lives => Ok(lives),
+    }
+}
+
 impl SupportedLanguage {
     fn as_lang(&self) -> Language {
         Language::new(self.to_string().to_lowercase())
@@ -75,16 +243,42 @@ impl SupportedLanguage {
 }
 
 struct App<'a> {
+    screen: Screen,
+    show_help: bool,
+    /// a plain text summary of every test finished, printed on the way out
+    /// in accessible mode
+    summaries: Vec<String>,
+    /// the last test that ran to the end, for `--print-results`
+    last_result: Option<TestResult>,
+    /// highlighted row of the menu or settings screen
+    selected: usize,
+    /// whether the first test, built from the command line, is yet to start
+    fresh: bool,
     cli: Option<Cli>,
+    config: Config,
+    layout: Layout,
+    lesson: Option<Lesson>,
+    /// translation from the keys the os sends to the practised layout
+    keymap: Option<HashMap<char, char>>,
     thok: Thok<'a>,
 }
 
-impl App<'_> {
-    fn new(cli: Cli) -> Self {
-        let mut count = 0;
-        let pace = cli.pace.map(|p| p as f64);
+impl<'a> App<'a> {
+    fn new(cli: Cli, config: Config, layout: Layout) -> Self {
+        let lesson = cli.lesson.then(|| {
+            let unlocked = lessons::load_progress()
+                .unwrap_or_default()
+                .get(layout.name())
+                .copied()
+                .unwrap_or(0);
+            Self::lesson(&cli, &config, &layout, unlocked)
+        });
+        let count;
+        let mut seed = None;
         let prompt = if cli.prompt.is_some() {
-            cli.prompt.clone().unwrap()
+            let prompt = cli.prompt.clone().unwrap();
+            count = prompt.chars().filter(|c| *c == ' ').count() + 1;
+            prompt
         } else if cli.number_of_sentences.is_some() {
             let language = cli.supported_language.as_lang();
             let (s, count_tmp) =
@@ -93,45 +287,58 @@ impl App<'_> {
             // sets the word count for the sentence.
             s.join("")
         } else {
-            let language = cli.supported_language.as_lang();
-
-            language.get_random(cli.number_of_words).join(" ")
+            let (words, word_seed) = Self::words(
+                &cli,
+                &config,
+                lesson.as_ref(),
+                cli.seed.unwrap_or_else(rand::random),
+            );
+            count = cli.number_of_words;
+            seed = word_seed;
+            words
         };
-        if cli.number_of_sentences.is_some() {
-            Self {
-                thok: Thok::new(
-                    prompt,
-                    count,
-                    cli.number_of_secs.map(|ns| ns as f64),
-                    pace,
-                    cli.death_mode,
-                ),
-                cli: Some(cli),
-            }
-        } else {
-            Self {
-                thok: Thok::new(
-                    prompt,
-                    cli.number_of_words,
-                    cli.number_of_secs.map(|ns| ns as f64),
-                    pace,
-                    cli.death_mode,
-                ),
-                cli: Some(cli),
-            }
+
+        let keymap = config.remap.then(|| Layout::qwerty().keymap_to(&layout));
+
+        Self {
+            screen: Screen::Menu,
+            show_help: false,
+            summaries: vec![],
+            last_result: None,
+            selected: 0,
+            fresh: true,
+            thok: Self::build_thok(
+                &cli,
+                &config,
+                &layout,
+                lesson.as_ref(),
+                prompt,
+                count,
+                seed,
+            ),
+            cli: Some(cli),
+            config,
+            layout,
+            lesson,
+            keymap,
         }
     }
 
     fn reset(&mut self, new_prompt: Option<String>) {
         let cli = self.cli.clone().unwrap();
-        let mut count = 0;
-        let pace = cli.pace.map(|p| p as f64);
+        let count;
+        let mut seed = None;
         let prompt = match new_prompt {
             Some(_) => {
                 let prompt = new_prompt.unwrap();
                 count = prompt.chars().filter(|c| *c == ' ').count() + 1;
                 prompt
             }
+            None if cli.prompt.is_some() => {
+                let prompt = cli.prompt.clone().unwrap();
+                count = prompt.chars().filter(|c| *c == ' ').count() + 1;
+                prompt
+            }
             _ => match cli.number_of_sentences {
                 Some(t) => {
                     let language = cli.supported_language.as_lang();
@@ -141,58 +348,308 @@ impl App<'_> {
                     s.join("")
                 }
                 _ => {
-                    let language = cli.supported_language.as_lang();
-                    language.get_random(cli.number_of_words).join(" ")
+                    let (words, word_seed) = Self::words(
+                        &cli,
+                        &self.config,
+                        self.lesson.as_ref(),
+                        // the seed only picks the words of the first test
+                        cli.seed
+                            .filter(|_| self.fresh)
+                            .unwrap_or_else(rand::random),
+                    );
+                    count = cli.number_of_words;
+                    seed = word_seed;
+                    words
                 }
             },
         };
-        if cli.number_of_sentences.is_some() {
-            self.thok = Thok::new(
-                prompt,
-                count,
-                cli.number_of_secs.map(|ns| ns as f64),
-                pace,
-                cli.death_mode,
-            );
+
+        self.thok = Self::build_thok(
+            &cli,
+            &self.config,
+            &self.layout,
+            self.lesson.as_ref(),
+            prompt,
+            count,
+            seed,
+        );
+    }
+
+    /// moves on to a new test. the first one was built along with the app, so
+    /// it's taken as it is
+    fn new_test(&mut self) {
+        if self.fresh {
+            // the countdown only starts once the test is on screen
+            self.thok.countdown =
+                self.thok.countdown.map(|_| SystemTime::now() + COUNTDOWN);
         } else {
-            self.thok = Thok::new(
-                prompt,
+            self.reset(None);
+        }
+        self.fresh = false;
+        self.screen = Screen::Test;
+    }
+
+    /// a prompt of words from the chosen language, along with the seed that
+    /// will pick the same words again. lessons and drills depend on more than
+    /// the seed, so no seed can repeat them
+    fn words(
+        cli: &Cli,
+        config: &Config,
+        lesson: Option<&Lesson>,
+        seed: u64,
+    ) -> (String, Option<u64>) {
+        let language = cli.supported_language.as_lang();
+
+        if let Some(lesson) = lesson {
+            let words = language
+                .get_using(
+                    &lesson.keys,
+                    lesson.focus(),
+                    cli.number_of_words,
+                    seed,
+                )
+                .join(" ");
+            return (words, None);
+        }
+
+        if cli.review {
+            let deck = review::load_deck().unwrap_or_default();
+            let words = review::pick(
+                &deck,
+                &language,
                 cli.number_of_words,
-                cli.number_of_secs.map(|ns| ns as f64),
-                pace,
-                cli.death_mode,
-            );
+                seed,
+                Local::now(),
+            )
+            .join(" ");
+            return (words, None);
+        }
+
+        match cli.drill {
+            Some(Drill::Ngrams) => {
+                let history =
+                    results::load_history(&config.logs).unwrap_or_default();
+                let targets = ngrams::drill_targets(
+                    history.iter().map(|r| r.keystrokes.as_slice()),
+                    ngrams::DRILL_TARGETS_PER_SIZE,
+                );
+                let words = language
+                    .get_containing(&targets, cli.number_of_words, seed)
+                    .join(" ");
+                (words, None)
+            }
+            None => (
+                language.get_random(cli.number_of_words, seed).join(" "),
+                Some(seed),
+            ),
         }
     }
+
+    fn lesson(
+        cli: &Cli,
+        config: &Config,
+        layout: &Layout,
+        unlocked: usize,
+    ) -> Lesson {
+        Lesson::new(
+            layout,
+            &cli.supported_language.as_lang(),
+            unlocked,
+            config.lesson_wpm,
+            config.lesson_accuracy,
+        )
+    }
+
+    /// wraps up a test that came to an end, scheduling its words for review
+    /// or unlocking the next key of a lesson when its targets were met
+    fn finish(&mut self) {
+        // a death mode run that ended early isn't scored
+        if self.thok.fatal_error() {
+            self.summaries.push(self.thok.plain_summary());
+            return;
+        }
+
+        self.thok.calc_results();
+        self.summaries.push(self.thok.plain_summary());
+        self.last_result = Some(TestResult::from_thok(&self.thok));
+
+        if self.cli.as_ref().is_some_and(|cli| cli.review) {
+            let mut deck = review::load_deck().unwrap_or_default();
+            review::record(&mut deck, &self.thok.word_stats(), Local::now());
+            let _ = review::save_deck(&deck);
+        }
+
+        let key_stats = self.thok.key_stats();
+        let Some(lesson) = &mut self.thok.lesson else {
+            return;
+        };
+        if lesson.assess(&key_stats) {
+            let unlocked = lesson.keys.len() + 1;
+            let mut progress = lessons::load_progress().unwrap_or_default();
+            progress.insert(self.layout.name().to_string(), unlocked);
+            let _ = lessons::save_progress(&progress);
+
+            let cli = self.cli.clone().unwrap();
+            self.lesson =
+                Some(Self::lesson(&cli, &self.config, &self.layout, unlocked));
+        }
+    }
+
+    /// the character the key would have produced on the practised layout
+    fn translate(&self, c: char) -> char {
+        self.keymap
+            .as_ref()
+            .and_then(|keymap| keymap.get(&c).copied())
+            .unwrap_or(c)
+    }
+
+    fn build_thok(
+        cli: &Cli,
+        config: &Config,
+        layout: &Layout,
+        lesson: Option<&Lesson>,
+        prompt: String,
+        number_of_words: usize,
+        seed: Option<u64>,
+    ) -> Thok<'a> {
+        let mut thok = Thok::new(
+            prompt,
+            number_of_words,
+            cli.number_of_secs.map(|ns| ns as f64),
+            cli.pace.map(|p| p as f64),
+            cli.death_rules(),
+            cli.strict,
+        );
+        thok.show_live_stats = config.live_stats;
+        thok.show_keyboard = config.keyboard;
+        thok.layout = layout.clone();
+        thok.lesson = lesson.cloned();
+        thok.keys = config.keys.clone();
+        thok.caret_style = config.caret;
+        thok.smooth_caret = config.smooth_caret;
+        thok.terminal_cursor = config.terminal_cursor;
+        thok.accessible = config.accessible;
+        thok.countdown =
+            config.countdown.then(|| SystemTime::now() + COUNTDOWN);
+        thok.log_formats = config.logs.clone();
+        thok.seed = seed;
+        // custom prompts don't come from any of the word lists
+        if cli.prompt.is_none() {
+            thok.language =
+                Some(cli.supported_language.to_string().to_lowercase());
+        }
+        thok
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
 
+    let mut config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            let mut cmd = Cli::command();
+            cmd.error(ErrorKind::Io, format!("unable to read config: {}", e))
+                .exit();
+        }
+    };
+
+    // importing and simulating don't use the layout, so a bad one in the
+    // config shouldn't stop them
+    match &cli.command {
+        Some(Command::Import(args)) => return import::run(args, &config.logs),
+        Some(Command::Simulate(args)) => return simulate::run(args),
+        _ => {}
+    }
+
+    if let Some(layout) = &cli.layout {
+        config.layout = layout.clone();
+    }
+    let layout = match Layout::load(&config.layout) {
+        Ok(layout) => layout,
+        Err(e) => {
+            let mut cmd = Cli::command();
+            cmd.error(ErrorKind::InvalidValue, e.to_string()).exit();
+        }
+    };
+
+    if let Some(Command::Stats(args)) = &cli.command {
+        return stats::run(args, &layout, &config.logs);
+    }
+
     if !stdin().is_tty() {
         let mut cmd = Cli::command();
         cmd.error(ErrorKind::Io, "stdin must be a tty").exit();
     }
+    if cli.no_live_stats {
+        config.live_stats = false;
+    }
+    config.remap |= cli.remap;
+    config.keyboard |= cli.keyboard;
+    config.countdown |= cli.countdown;
+    config.restart_on_typo |= cli.restart_on_typo;
+    config.smooth_caret |= cli.smooth_caret;
+    config.accessible |= cli.accessible;
+    config.terminal_cursor |= cli.terminal_cursor;
+    if let Some(caret) = cli.caret {
+        config.caret = caret;
+    }
 
     enable_raw_mode()?;
 
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
+    // stdout is kept for the results when they're printed, so the tui is
+    // drawn straight on the terminal instead
+    let mut out: Box<dyn Write> = match cli.print_results {
+        Some(_) => match OpenOptions::new().write(true).open("/dev/tty") {
+            Ok(tty) => Box::new(tty),
+            Err(_) => Box::new(io::stderr()),
+        },
+        None => Box::new(io::stdout()),
+    };
+    execute!(out, EnterAlternateScreen)?;
+    // the terminal's cursor takes the shape of the caret it stands in for
+    let cursor_shape = match config.caret {
+        _ if !config.terminal_cursor && config.caret != CaretStyle::Bar => None,
+        CaretStyle::Block => Some(CursorShape::Block),
+        CaretStyle::Underline => Some(CursorShape::UnderScore),
+        CaretStyle::Bar => Some(CursorShape::Line),
+        CaretStyle::Off => None,
+    };
+    if let Some(shape) = cursor_shape {
+        execute!(out, SetCursorShape(shape))?;
+    }
+    let backend = CrosstermBackend::new(out);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new(cli);
+    let mut app = App::new(cli, config, layout);
     start_tui(&mut terminal, &mut app)?;
 
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen,)?;
+    if cursor_shape.is_some() {
+        // back to whatever cursor shape the terminal is set up with
+        write!(terminal.backend_mut(), "\x1b[0 q")?;
+    }
     terminal.show_cursor()?;
 
+    // the alternate screen is gone, so this stays behind for screen readers
+    if app.config.accessible {
+        for (idx, summary) in app.summaries.iter().enumerate() {
+            writeln!(terminal.backend_mut(), "test {}: {}", idx + 1, summary)?;
+        }
+        Write::flush(terminal.backend_mut())?;
+    }
+    let print_format = app.cli.as_ref().and_then(|cli| cli.print_results);
+    if let (Some(format), Some(result)) = (print_format, &app.last_result) {
+        println!("{}", result.print_as(format)?);
+    }
+
     Ok(())
 }
 
 enum ExitType {
     Restart,
+    RetryMissed,
     New,
     Quit,
 }
@@ -200,12 +657,7 @@ fn start_tui<B: Backend>(
     terminal: &mut Terminal<B>,
     mut app: &mut App,
 ) -> Result<(), Box<dyn Error>> {
-    let cli = app.cli.clone().expect("Expected CLI");
-
-    let should_tick =
-        cli.number_of_secs.unwrap_or(0) > 0 || matches!(cli.pace, Some(_v));
-
-    let thok_events = get_thok_events(should_tick);
+    let thok_events = get_thok_events();
 
     loop {
         let mut exit_type: ExitType = ExitType::Quit;
@@ -216,11 +668,24 @@ fn start_tui<B: Backend>(
 
             match thok_events.recv()? {
                 ThokEvent::Tick => {
-                    if app.thok.has_started() && !app.thok.has_finished() {
+                    if app.screen == Screen::Test
+                        && app.thok.countdown.is_some()
+                        && !app.thok.has_started()
+                    {
+                        // the timer starts when the countdown runs out
+                        // rather than on the first key
+                        if !app.thok.counting_down() {
+                            app.thok.start();
+                        }
+                        terminal.draw(|f| ui(app, f))?;
+                    } else if app.screen == Screen::Test
+                        && app.thok.has_started()
+                        && !app.thok.has_finished()
+                    {
                         app.thok.on_tick();
 
-                        if app.thok.has_finished() && !app.thok.fatal_error() {
-                            app.thok.calc_results();
+                        if app.thok.has_finished() {
+                            app.finish();
                         }
                         terminal.draw(|f| ui(app, f))?;
                     }
@@ -229,68 +694,140 @@ fn start_tui<B: Backend>(
                     terminal.draw(|f| ui(app, f))?;
                 }
                 ThokEvent::Key(key) => {
-                    match key.code {
-                        KeyCode::Esc => {
-                            break;
-                        }
-                        KeyCode::Backspace => {
-                            if !app.thok.has_finished() {
-                                app.thok.backspace();
-                            }
-                        }
-                        KeyCode::Left => {
-                            exit_type = ExitType::Restart;
-                            break;
-                        }
-                        KeyCode::Right => {
-                            exit_type = ExitType::New;
-                            break;
+                    let context = match app.screen {
+                        Screen::Test
+                            if app.thok.has_finished() || app.thok.tabbed =>
+                        {
+                            Context::Options
                         }
-                        KeyCode::Tab => {
-                            if !app.thok.has_finished() {
-                                app.thok.tabbed = !app.thok.tabbed;
-                            }
-                        }
-                        KeyCode::Char(c) => {
-                            if key.modifiers.contains(KeyModifiers::CONTROL)
-                                && c == 'c'
-                            // ctrl+c to quit
-                            {
-                                break;
-                            }
-
-                            match app.thok.has_finished() || app.thok.tabbed {
-                                false => {
-                                    if key
-                                        .modifiers
-                                        .contains(KeyModifiers::CONTROL)
-                                        && c == 'h'
-                                    {
-                                        app.thok.word_backspace();
-                                    } else {
-                                        app.thok.write(c);
-                                    }
+                        Screen::Test => Context::Typing,
+                        Screen::Menu | Screen::Settings => Context::Menus,
+                    };
+                    let options_open = context == Context::Options;
+                    let action = app.config.keys.action(&key, context);
+
+                    if action == Some(Action::Quit) {
+                        break;
+                    }
 
-                                    if app.thok.has_finished()
-                                        && !app.thok.fatal_error()
-                                    {
-                                        app.thok.calc_results();
+                    if app.show_help {
+                        // any key closes the help
+                        app.show_help = false;
+                    } else if action == Some(Action::Help) {
+                        app.show_help = true;
+                    } else {
+                        match app.screen {
+                            Screen::Menu => match action {
+                                Some(Action::Up) => {
+                                    app.selected =
+                                        app.selected.saturating_sub(1);
+                                }
+                                Some(Action::Down) => {
+                                    app.selected = (app.selected + 1)
+                                        .min(MENU_ITEMS.len() - 1);
+                                }
+                                Some(Action::Select) => {
+                                    match MENU_ITEMS[app.selected] {
+                                        MenuItem::Start => {
+                                            exit_type = ExitType::New;
+                                            break;
+                                        }
+                                        MenuItem::Settings => {
+                                            app.screen = Screen::Settings;
+                                            app.selected = 0;
+                                        }
+                                        MenuItem::Quit => break,
+                                    }
+                                }
+                                Some(Action::Menu | Action::Back) => break,
+                                _ => {}
+                            },
+                            Screen::Settings => match action {
+                                Some(Action::Up) => {
+                                    app.selected =
+                                        app.selected.saturating_sub(1);
+                                }
+                                Some(Action::Down) => {
+                                    app.selected = (app.selected + 1)
+                                        .min(SETTINGS.len() - 1);
+                                }
+                                Some(Action::Decrease | Action::Increase) => {
+                                    let cli =
+                                        app.cli.as_mut().expect("Expected CLI");
+                                    SETTINGS[app.selected].adjust(
+                                        cli,
+                                        action == Some(Action::Increase),
+                                    );
+                                    // the first test follows the settings
+                                    if app.fresh {
+                                        app.reset(None);
                                     }
                                 }
-                                true => match key.code {
-                                    KeyCode::Char('r') => {
-                                        exit_type = ExitType::Restart;
-                                        break;
+                                Some(
+                                    Action::Menu
+                                    | Action::Back
+                                    | Action::Select,
+                                ) => {
+                                    app.screen = Screen::Menu;
+                                    app.selected = 0;
+                                }
+                                _ => {}
+                            },
+                            Screen::Test => match action {
+                                Some(Action::Menu) => {
+                                    app.screen = Screen::Menu;
+                                    app.selected = 0;
+                                }
+                                Some(Action::Restart | Action::Retry) => {
+                                    exit_type = ExitType::Restart;
+                                    break;
+                                }
+                                Some(Action::New | Action::Next) => {
+                                    exit_type = ExitType::New;
+                                    break;
+                                }
+                                Some(Action::Options)
+                                    if !app.thok.has_finished() =>
+                                {
+                                    app.thok.tabbed = !app.thok.tabbed;
+                                }
+                                Some(Action::WordDelete) if !options_open => {
+                                    app.thok.word_backspace();
+                                }
+                                Some(Action::Missed)
+                                    if app.thok.has_finished()
+                                        && !app
+                                            .thok
+                                            .missed_words()
+                                            .is_empty() =>
+                                {
+                                    exit_type = ExitType::RetryMissed;
+                                    break;
+                                }
+                                _ => match key.code {
+                                    KeyCode::Backspace
+                                        if !app.thok.has_finished() =>
+                                    {
+                                        app.thok.backspace();
                                     }
-                                    KeyCode::Char('n') => {
-                                        exit_type = ExitType::New;
-                                        break;
+                                    KeyCode::Char(c) if !options_open => {
+                                        app.thok.write(app.translate(c));
+
+                                        if app.config.restart_on_typo
+                                            && app.thok.missed_first_word()
+                                        {
+                                            exit_type = ExitType::Restart;
+                                            break;
+                                        }
+
+                                        if app.thok.has_finished() {
+                                            app.finish();
+                                        }
                                     }
                                     _ => {}
                                 },
-                            }
+                            },
                         }
-                        _ => {}
                     }
                     terminal.draw(|f| ui(app, f))?;
                 }
@@ -299,11 +836,15 @@ fn start_tui<B: Backend>(
 
         match exit_type {
             ExitType::Restart => {
+                let seed = app.thok.seed;
                 app.reset(Some(app.thok.prompt.clone()));
+                // same words as before, so the same seed still applies
+                app.thok.seed = seed;
             }
-            ExitType::New => {
-                app.reset(None);
+            ExitType::RetryMissed => {
+                app.reset(Some(app.thok.missed_words().join(" ")));
             }
+            ExitType::New => app.new_test(),
             ExitType::Quit => {
                 break;
             }
@@ -320,19 +861,19 @@ enum ThokEvent {
     Tick,
 }
 
-fn get_thok_events(should_tick: bool) -> mpsc::Receiver<ThokEvent> {
+/// key presses and resizes, along with a tick that drives timers and pacing.
+/// the tick always runs since the settings screen can switch either on
+fn get_thok_events() -> mpsc::Receiver<ThokEvent> {
     let (tx, rx) = mpsc::channel();
 
-    if should_tick {
-        let tick_x = tx.clone();
-        thread::spawn(move || loop {
-            if tick_x.send(ThokEvent::Tick).is_err() {
-                break;
-            }
+    let tick_x = tx.clone();
+    thread::spawn(move || loop {
+        if tick_x.send(ThokEvent::Tick).is_err() {
+            break;
+        }
 
-            thread::sleep(Duration::from_millis(TICK_RATE_MS))
-        });
-    }
+        thread::sleep(Duration::from_millis(TICK_RATE_MS))
+    });
 
     thread::spawn(move || loop {
         let evt = match event::read().unwrap() {
@@ -350,5 +891,106 @@ fn get_thok_events(should_tick: bool) -> mpsc::Receiver<ThokEvent> {
 }
 
 fn ui(app: &mut App, f: &mut Frame) {
-    f.render_widget(&app.thok, f.size());
+    match app.screen {
+        Screen::Test => f.render_widget(&app.thok, f.size()),
+        Screen::Menu => f.render_widget(
+            Menu {
+                title: "thokr",
+                rows: MENU_ITEMS
+                    .iter()
+                    .map(|item| (item.label().to_string(), None))
+                    .collect(),
+                selected: app.selected,
+                legend: &format!(
+                    "({}/{}) select / ({}) choose / ({}) quit / ({}) help",
+                    app.config.keys.key(Action::Up),
+                    app.config.keys.key(Action::Down),
+                    app.config.keys.key(Action::Select),
+                    app.config.keys.key(Action::Menu),
+                    app.config.keys.key(Action::Help)
+                ),
+            },
+            f.size(),
+        ),
+        Screen::Settings => {
+            let cli = app.cli.as_ref().expect("Expected CLI");
+            f.render_widget(
+                Menu {
+                    title: "settings",
+                    rows: SETTINGS
+                        .iter()
+                        .map(|s| (s.label().to_string(), Some(s.value(cli))))
+                        .collect(),
+                    selected: app.selected,
+                    legend: &format!(
+                        "({}/{}) select / ({}/{}) change / ({}) back",
+                        app.config.keys.key(Action::Up),
+                        app.config.keys.key(Action::Down),
+                        app.config.keys.key(Action::Decrease),
+                        app.config.keys.key(Action::Increase),
+                        app.config.keys.key(Action::Menu)
+                    ),
+                },
+                f.size(),
+            )
+        }
+    }
+
+    if app.show_help {
+        f.render_widget(Help(&app.config.keys), f.size());
+    } else if let Some((x, y)) = app.thok.cursor_cell.get() {
+        if app.screen == Screen::Test {
+            f.set_cursor(x, y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_with(args: &[&str]) -> App<'static> {
+        let cli = Cli::parse_from([&["thokr"], args].concat());
+        App::new(cli, Config::default(), Layout::qwerty())
+    }
+
+    #[test]
+    fn test_first_test() {
+        let mut app = app_with(&["-p", "hello world"]);
+        app.new_test();
+        assert_eq!(app.thok.prompt, "hello world");
+        // a custom prompt is used for every test
+        app.new_test();
+        assert_eq!(app.thok.prompt, "hello world");
+        // and is scored by its own words, not by -w
+        assert_eq!(app.thok.number_of_words, 2);
+        app.reset(Some("one more retry".to_string()));
+        assert_eq!(app.thok.number_of_words, 3);
+
+        let mut app = app_with(&["--seed", "7", "-w", "5"]);
+        let words = app.thok.prompt.clone();
+        app.new_test();
+        assert_eq!(
+            (app.thok.prompt.as_str(), app.thok.seed),
+            (&*words, Some(7))
+        );
+        app.new_test();
+        assert_ne!(app.thok.seed, Some(7));
+
+        // changing the settings before starting keeps the seed
+        let mut app = app_with(&["--seed", "7", "-w", "5"]);
+        SETTINGS[1].adjust(app.cli.as_mut().unwrap(), true);
+        app.reset(None);
+        app.new_test();
+        assert_eq!(app.thok.seed, Some(7));
+        assert_eq!(app.thok.prompt.split(' ').count(), 10);
+    }
+
+    #[test]
+    fn test_lives() {
+        let cli = Cli::try_parse_from(["thokr", "--lives", "2"]).unwrap();
+        assert_eq!(cli.death_rules().unwrap().lives, Some(2));
+        assert!(Cli::try_parse_from(["thokr", "--lives", "0"]).is_err());
+        assert!(Cli::try_parse_from(["thokr", "--lives", "x"]).is_err());
+    }
 }
@@ -0,0 +1,257 @@
+use crate::{Cli, SupportedLanguage};
+use clap::ArgEnum;
+
+/// what the tui is showing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Screen {
+    Menu,
+    Settings,
+    Test,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MenuItem {
+    Start,
+    Settings,
+    Quit,
+}
+
+pub const MENU_ITEMS: [MenuItem; 3] =
+    [MenuItem::Start, MenuItem::Settings, MenuItem::Quit];
+
+impl MenuItem {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MenuItem::Start => "start",
+            MenuItem::Settings => "settings",
+            MenuItem::Quit => "quit",
+        }
+    }
+}
+
+/// how the prompt of a test is made and when the test ends
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Words,
+    Sentences,
+    Time,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Setting {
+    Mode,
+    Words,
+    Sentences,
+    Seconds,
+    Language,
+    Pace,
+    DeathMode,
+}
+
+/// there is no theme setting, since thokr has no themes: its colours are the
+/// terminal's own named colours, so the terminal's palette already sets them
+pub const SETTINGS: [Setting; 7] = [
+    Setting::Mode,
+    Setting::Words,
+    Setting::Sentences,
+    Setting::Seconds,
+    Setting::Language,
+    Setting::Pace,
+    Setting::DeathMode,
+];
+
+const SECONDS: [usize; 4] = [15, 30, 60, 120];
+const PACE_STEP: u16 = 10;
+const MIN_PACE: u16 = 40;
+const MAX_PACE: u16 = 200;
+
+fn mode(cli: &Cli) -> Mode {
+    if cli.number_of_secs.is_some() {
+        Mode::Time
+    } else if cli.number_of_sentences.is_some() {
+        Mode::Sentences
+    } else {
+        Mode::Words
+    }
+}
+
+/// the next (or previous) item of `items` after `current`, wrapping around
+fn cycle<T: PartialEq + Copy>(items: &[T], current: T, forward: bool) -> T {
+    let idx = items.iter().position(|i| *i == current).unwrap_or(0);
+    let next = if forward {
+        (idx + 1) % items.len()
+    } else {
+        (idx + items.len() - 1) % items.len()
+    };
+
+    items[next]
+}
+
+impl Setting {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Setting::Mode => "mode",
+            Setting::Words => "words",
+            Setting::Sentences => "sentences",
+            Setting::Seconds => "seconds",
+            Setting::Language => "language",
+            Setting::Pace => "pace",
+            Setting::DeathMode => "death mode",
+        }
+    }
+
+    /// whether the setting changes the next test. a prompt given with `-p`
+    /// is typed as it is, so the settings that pick the words don't
+    pub fn applies(&self, cli: &Cli) -> bool {
+        cli.prompt.is_none()
+            || !matches!(
+                self,
+                Setting::Words | Setting::Sentences | Setting::Language
+            )
+    }
+
+    pub fn value(&self, cli: &Cli) -> String {
+        let or_off = |value: Option<String>| value.unwrap_or("off".to_string());
+        if !self.applies(cli) {
+            return String::from("ignored with -p");
+        }
+
+        match self {
+            Setting::Mode => match mode(cli) {
+                Mode::Words => "words",
+                Mode::Sentences => "sentences",
+                Mode::Time => "time",
+            }
+            .to_string(),
+            Setting::Words => cli.number_of_words.to_string(),
+            Setting::Sentences => {
+                or_off(cli.number_of_sentences.map(|s| s.to_string()))
+            }
+            Setting::Seconds => {
+                or_off(cli.number_of_secs.map(|s| s.to_string()))
+            }
+            Setting::Language => {
+                cli.supported_language.to_string().to_lowercase()
+            }
+            Setting::Pace => or_off(cli.pace.map(|p| format!("{} wpm", p))),
+            Setting::DeathMode => {
+                or_off(cli.death_rules().map(|_| "on".to_string()))
+            }
+        }
+    }
+
+    /// steps the setting to its next (or previous) value
+    pub fn adjust(&self, cli: &mut Cli, forward: bool) {
+        if !self.applies(cli) {
+            return;
+        }
+
+        match self {
+            Setting::Mode => {
+                let modes = [Mode::Words, Mode::Sentences, Mode::Time];
+                let (sentences, secs) = match cycle(&modes, mode(cli), forward)
+                {
+                    Mode::Words => (None, None),
+                    Mode::Sentences => (Some(1), None),
+                    Mode::Time => (None, Some(SECONDS[1])),
+                };
+                cli.number_of_sentences = sentences;
+                cli.number_of_secs = secs;
+            }
+            Setting::Words => {
+                cli.number_of_words = if forward {
+                    cli.number_of_words + 5
+                } else {
+                    cli.number_of_words.saturating_sub(5).max(5)
+                };
+            }
+            Setting::Sentences => {
+                let sentences = cli.number_of_sentences.unwrap_or(1);
+                // picking a number of sentences switches to sentence mode
+                cli.number_of_sentences = Some(if forward {
+                    sentences + 1
+                } else {
+                    sentences.saturating_sub(1).max(1)
+                });
+                cli.number_of_secs = None;
+            }
+            Setting::Seconds => {
+                let secs = cli.number_of_secs.unwrap_or(SECONDS[0]);
+                // picking a duration switches to timed mode
+                cli.number_of_secs = Some(cycle(&SECONDS, secs, forward));
+                cli.number_of_sentences = None;
+            }
+            Setting::Language => {
+                cli.supported_language = cycle(
+                    SupportedLanguage::value_variants(),
+                    cli.supported_language,
+                    forward,
+                );
+            }
+            Setting::Pace => {
+                cli.pace = match (cli.pace, forward) {
+                    (None, true) => Some(MIN_PACE),
+                    (None, false) => Some(MAX_PACE),
+                    (Some(MAX_PACE..), true) => None,
+                    (Some(..=MIN_PACE), false) => None,
+                    (Some(p), true) => Some(p + PACE_STEP),
+                    (Some(p), false) => Some(p - PACE_STEP),
+                };
+            }
+            Setting::DeathMode => {
+                let on = cli.death_rules().is_none();
+                cli.death_mode = on;
+                cli.lives = None;
+                cli.min_accuracy = None;
+                cli.min_wpm = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_adjust() {
+        let mut cli = Cli::parse_from(["thokr", "--lives", "3"]);
+
+        Setting::Mode.adjust(&mut cli, false);
+        assert_eq!(Setting::Mode.value(&cli), "time");
+        assert_eq!(cli.number_of_secs, Some(30));
+        Setting::Seconds.adjust(&mut cli, true);
+        assert_eq!(cli.number_of_secs, Some(60));
+        Setting::Sentences.adjust(&mut cli, true);
+        assert_eq!(Setting::Mode.value(&cli), "sentences");
+        assert_eq!(cli.number_of_sentences, Some(2));
+
+        Setting::Words.adjust(&mut cli, false);
+        Setting::Words.adjust(&mut cli, false);
+        Setting::Words.adjust(&mut cli, false);
+        assert_eq!(cli.number_of_words, 5);
+
+        Setting::Language.adjust(&mut cli, false);
+        assert_eq!(Setting::Language.value(&cli), "english10k");
+
+        Setting::Pace.adjust(&mut cli, false);
+        assert_eq!(cli.pace, Some(200));
+        Setting::Pace.adjust(&mut cli, true);
+        assert_eq!(Setting::Pace.value(&cli), "off");
+
+        assert_eq!(Setting::DeathMode.value(&cli), "on");
+        Setting::DeathMode.adjust(&mut cli, true);
+        assert_eq!(cli.death_rules(), None);
+        Setting::DeathMode.adjust(&mut cli, true);
+        assert_eq!(cli.death_rules().unwrap().lives, Some(1));
+
+        // a custom prompt leaves nothing for the word settings to change
+        let mut cli = Cli::parse_from(["thokr", "-p", "hello world"]);
+        Setting::Words.adjust(&mut cli, true);
+        assert_eq!(cli.number_of_words, 15);
+        assert_eq!(Setting::Language.value(&cli), "ignored with -p");
+        Setting::Seconds.adjust(&mut cli, true);
+        assert_eq!(cli.number_of_secs, Some(30));
+    }
+}
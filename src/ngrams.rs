@@ -0,0 +1,138 @@
+use crate::results::Keystroke;
+use crate::util::mean;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// n-grams seen fewer times than this are too noisy to call slow
+pub const MIN_NGRAM_COUNT: usize = 3;
+/// how many of the slowest bigrams, and of the slowest trigrams, a drill
+/// works on at once
+pub const DRILL_TARGETS_PER_SIZE: usize = 3;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct NgramStats {
+    pub ngram: String,
+    pub count: usize,
+    /// mean time from the first key of the n-gram to the last, in
+    /// milliseconds
+    pub mean_ms: f64,
+}
+
+/// latency of every n-gram (of two or more letters) typed without a
+/// mistake, slowest first. n-grams seen fewer than `MIN_NGRAM_COUNT` times
+/// are left out
+pub fn latencies<'a>(
+    tests: impl IntoIterator<Item = &'a [Keystroke]>,
+    n: usize,
+) -> Vec<NgramStats> {
+    let mut timings: HashMap<String, Vec<f64>> = HashMap::new();
+
+    for keystrokes in tests {
+        for window in keystrokes.windows(n) {
+            if !window
+                .iter()
+                .all(|k| k.expected == k.typed && k.expected.is_alphabetic())
+            {
+                continue;
+            }
+
+            let ngram = window
+                .iter()
+                .flat_map(|k| k.expected.to_lowercase())
+                .collect::<String>();
+            let elapsed = window[n - 1].ms.saturating_sub(window[0].ms);
+            timings.entry(ngram).or_default().push(elapsed as f64);
+        }
+    }
+
+    let mut stats = timings
+        .into_iter()
+        .filter(|(_, times)| times.len() >= MIN_NGRAM_COUNT)
+        .filter_map(|(ngram, times)| {
+            Some(NgramStats {
+                ngram,
+                count: times.len(),
+                mean_ms: mean(&times)?,
+            })
+        })
+        .collect::<Vec<NgramStats>>();
+    // ties go alphabetically so the order doesn't depend on the hashing
+    stats.sort_by(|a, b| {
+        b.mean_ms.total_cmp(&a.mean_ms).then(a.ngram.cmp(&b.ngram))
+    });
+
+    stats
+}
+
+/// the slowest bigrams and trigrams worth drilling, `per_size` of each
+pub fn drill_targets<'a>(
+    tests: impl IntoIterator<Item = &'a [Keystroke]> + Clone,
+    per_size: usize,
+) -> Vec<String> {
+    [2, 3]
+        .into_iter()
+        .flat_map(|n| {
+            latencies(tests.clone(), n)
+                .into_iter()
+                .take(per_size)
+                .map(|stats| stats.ngram)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keystrokes(text: &str, gaps: &[u64]) -> Vec<Keystroke> {
+        let mut ms = 0;
+        text.chars()
+            .zip(gaps)
+            .map(|(c, gap)| {
+                ms += gap;
+                Keystroke {
+                    expected: c,
+                    typed: c,
+                    ms,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_latencies() {
+        let fast = keystrokes("the", &[0, 50, 50]);
+        let slow = keystrokes("The", &[0, 200, 100]);
+        let mut typo = keystrokes("the", &[0, 900, 900]);
+        typo[1].typed = 'j';
+        let tests = [
+            fast.as_slice(),
+            slow.as_slice(),
+            slow.as_slice(),
+            typo.as_slice(),
+        ];
+
+        let bigrams = latencies(tests, 2);
+        assert_eq!(
+            bigrams,
+            vec![
+                NgramStats {
+                    ngram: "th".to_string(),
+                    count: 3,
+                    mean_ms: 150.0,
+                },
+                NgramStats {
+                    ngram: "he".to_string(),
+                    count: 3,
+                    mean_ms: 250.0 / 3.0,
+                },
+            ]
+        );
+        assert_eq!(latencies(tests, 3)[0].mean_ms, 700.0 / 3.0);
+        assert_eq!(drill_targets(tests, 1), vec!["th", "the"]);
+
+        // spaces break n-grams up
+        let spaced = keystrokes("a b", &[0, 10, 10]);
+        assert!(latencies([spaced.as_slice(); 3], 2).is_empty());
+    }
+}
@@ -0,0 +1,433 @@
+use crate::db;
+use crate::thok::{DeathRules, Input, KeyStats, Outcome, StrictMode, Thok};
+use chrono::prelude::*;
+use clap::ArgEnum;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// formats the results of finished tests can be logged in
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// `log.csv`, one row of headline numbers per test
+    Csv,
+    /// `log.jsonl`, one json object with every detail per test
+    Json,
+    /// `history.db`, a sqlite database of runs, keystrokes and per-key stats
+    Sqlite,
+}
+
+/// how the last test is printed on the way out, for scripts and status lines
+#[derive(Clone, Copy, Debug, PartialEq, ArgEnum)]
+pub enum PrintFormat {
+    /// the headline numbers on one line, like `72 wpm 96% acc 1.23 sd`
+    Text,
+    /// the same json object `log.jsonl` keeps, on one line
+    Json,
+}
+
+/// a single accepted keystroke, timed from the start of the test
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Keystroke {
+    pub expected: char,
+    pub typed: char,
+    pub ms: u64,
+}
+
+impl Keystroke {
+    /// the keystrokes still standing in a test, in the order they were typed.
+    /// letters typed past the end of a word were expected to be its space
+    pub fn from_thok(thok: &Thok) -> Vec<Self> {
+        let ms = |input: &Input| {
+            thok.started_at
+                .and_then(|s| input.timestamp.duration_since(s).ok())
+                .map_or(0, |d| d.as_millis() as u64)
+        };
+        let keystroke = |input: &Input, expected| Self {
+            expected,
+            typed: input.char,
+            ms: ms(input),
+        };
+
+        let mut prompt = thok.prompt.chars();
+        let mut keystrokes = vec![];
+        for idx in 0..=thok.input.len() {
+            for input in thok.overflow.get(&idx).into_iter().flatten() {
+                keystrokes.push(keystroke(input, ' '));
+            }
+            let (Some(input), Some(expected)) =
+                (thok.input.get(idx), prompt.next())
+            else {
+                break;
+            };
+            // skipped letters were never pressed
+            if input.outcome != Outcome::Skipped {
+                keystrokes.push(keystroke(input, expected));
+            }
+        }
+
+        // a space that skipped the rest of the last word ended the test. the
+        // prompt has no space after it to hold one, so it's only marked by
+        // the letters it skipped, which share its timestamp
+        if let Some(first_skipped) = thok
+            .input
+            .iter()
+            .rev()
+            .take_while(|input| input.outcome == Outcome::Skipped)
+            .last()
+        {
+            keystrokes.push(Self {
+                expected: first_skipped.char,
+                typed: ' ',
+                ms: ms(first_skipped),
+            });
+        }
+
+        keystrokes
+    }
+}
+
+/// everything recorded about a finished test
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TestResult {
+    pub date: DateTime<Local>,
+    pub language: Option<String>,
+    pub seed: Option<u64>,
+    /// what was to be typed, so the test can be replayed
+    #[serde(default)]
+    pub prompt: Option<String>,
+    pub num_words: usize,
+    pub num_secs: Option<f64>,
+    pub pace: Option<f64>,
+    pub strict: Option<StrictMode>,
+    pub death_mode: Option<DeathRules>,
+    pub elapsed_secs: f64,
+    pub wpm: f64,
+    pub accuracy: f64,
+    /// left out by tools that don't measure it
+    pub std_dev: Option<f64>,
+    pub mistakes: usize,
+    #[serde(default)]
+    pub keys: BTreeMap<char, KeyStats>,
+    #[serde(default)]
+    pub keystrokes: Vec<Keystroke>,
+}
+
+impl TestResult {
+    pub fn from_thok(thok: &Thok) -> Self {
+        Self {
+            date: Local::now(),
+            language: thok.language.clone(),
+            seed: thok.seed,
+            prompt: Some(thok.prompt.clone()),
+            num_words: thok.number_of_words,
+            num_secs: thok.number_of_secs,
+            pace: thok.pace,
+            strict: thok.strict,
+            death_mode: thok.death_mode,
+            elapsed_secs: thok.elapsed().map_or(0.0, |e| e.as_secs_f64()),
+            wpm: thok.wpm,
+            accuracy: thok.accuracy,
+            std_dev: Some(thok.std_dev),
+            mistakes: thok.mistakes,
+            keys: thok.key_stats(),
+            keystrokes: Keystroke::from_thok(thok),
+        }
+    }
+
+    pub fn print_as(&self, format: PrintFormat) -> serde_json::Result<String> {
+        Ok(match format {
+            PrintFormat::Text => format!(
+                "{} wpm {}% acc {} sd",
+                self.wpm,
+                self.accuracy,
+                self.std_dev
+                    .map_or(String::from("-"), |sd| format!("{:.2}", sd))
+            ),
+            PrintFormat::Json => serde_json::to_string(self)?,
+        })
+    }
+
+    /// reads a row of `log.csv`. only the headline numbers are logged there,
+    /// so everything else is left empty
+    pub fn from_csv_row(row: &str) -> Option<Self> {
+        let fields = row.split(',').collect::<Vec<&str>>();
+        let [date, num_words, num_secs, elapsed_secs, wpm, accuracy, std_dev] =
+            fields[..]
+        else {
+            return None;
+        };
+
+        let date = NaiveDateTime::parse_from_str(date, "%c").ok()?;
+
+        Some(Self {
+            num_words: num_words.parse().ok()?,
+            num_secs: match num_secs {
+                "" => None,
+                ns => Some(ns.parse().ok()?),
+            },
+            elapsed_secs: elapsed_secs.parse().ok()?,
+            wpm: wpm.parse().ok()?,
+            accuracy: accuracy.parse().ok()?,
+            std_dev: match std_dev {
+                "" => None,
+                sd => Some(sd.parse().ok()?),
+            },
+            ..Self::bare(Local.from_local_datetime(&date).earliest()?)
+        })
+    }
+
+    /// a result with nothing but a date, for filling in from logs that only
+    /// kept the headline numbers
+    pub fn bare(date: DateTime<Local>) -> Self {
+        Self {
+            date,
+            language: None,
+            seed: None,
+            prompt: None,
+            num_words: 0,
+            num_secs: None,
+            pace: None,
+            strict: None,
+            death_mode: None,
+            elapsed_secs: 0.0,
+            wpm: 0.0,
+            accuracy: 0.0,
+            std_dev: None,
+            mistakes: 0,
+            keys: BTreeMap::new(),
+            keystrokes: vec![],
+        }
+    }
+}
+
+/// folder the logs are kept in, next to the config file
+pub fn log_dir() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "thokr").map(|p| p.config_dir().to_path_buf())
+}
+
+/// every logged result, oldest first
+pub fn load_history(
+    log_formats: &[LogFormat],
+) -> Result<Vec<TestResult>, Box<dyn Error>> {
+    match log_dir() {
+        Some(log_dir) => load_history_from(&log_dir, log_formats),
+        None => Ok(vec![]),
+    }
+}
+
+/// every result logged in `log_dir`, oldest first. all of the logs being
+/// written are read, since one switched on later only holds the tests since.
+/// a test kept in more than one of them is taken from the most detailed: the
+/// database, then the json lines log, then the csv log. the csv log only
+/// keeps dates to the second, so that's what they're matched by
+fn load_history_from(
+    log_dir: &Path,
+    log_formats: &[LogFormat],
+) -> Result<Vec<TestResult>, Box<dyn Error>> {
+    let db_path = log_dir.join("history.db");
+    let json_path = log_dir.join("log.jsonl");
+    let csv_path = log_dir.join("log.csv");
+    let enabled =
+        |format, path: &Path| log_formats.contains(&format) && path.exists();
+
+    let mut logs = vec![];
+    if enabled(LogFormat::Sqlite, &db_path) {
+        logs.push(db::load_results(&db::open(&db_path)?)?);
+    }
+    if enabled(LogFormat::Json, &json_path) {
+        logs.push(
+            fs::read_to_string(json_path)?
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<Vec<TestResult>, _>>()?,
+        );
+    }
+    if enabled(LogFormat::Csv, &csv_path) {
+        logs.push(
+            fs::read_to_string(csv_path)?
+                .lines()
+                .filter_map(TestResult::from_csv_row)
+                .collect(),
+        );
+    }
+
+    let mut history: Vec<TestResult> = vec![];
+    for log in logs {
+        let seen = history
+            .iter()
+            .map(|r| r.date.timestamp())
+            .collect::<HashSet<i64>>();
+        history.extend(
+            log.into_iter()
+                .filter(|r| !seen.contains(&r.date.timestamp())),
+        );
+    }
+    history.sort_by_key(|r| r.date);
+
+    Ok(history)
+}
+
+/// appends results to each of the given logs
+pub fn save(
+    results: &[TestResult],
+    log_formats: &[LogFormat],
+) -> Result<(), Box<dyn Error>> {
+    let Some(log_dir) = log_dir() else {
+        return Ok(());
+    };
+    fs::create_dir_all(&log_dir)?;
+
+    // the database goes first, so that the first time it is used it picks up
+    // the csv log as it was before these results
+    if log_formats.contains(&LogFormat::Sqlite) {
+        let mut conn = db::open(&log_dir.join("history.db"))?;
+        db::import_csv_once(&mut conn, &log_dir.join("log.csv"))?;
+        for result in results {
+            db::insert_result(&mut conn, result)?;
+        }
+    }
+    if log_formats.contains(&LogFormat::Csv) {
+        for result in results {
+            append_csv(&log_dir.join("log.csv"), result)?;
+        }
+    }
+    if log_formats.contains(&LogFormat::Json) {
+        for result in results {
+            append_json(&log_dir.join("log.jsonl"), result)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// appends a row to the csv log, writing the header first for a new file
+pub fn append_csv(log_path: &Path, result: &TestResult) -> io::Result<()> {
+    // If the log file doesn't exist, we need to emit a header
+    let needs_header = !log_path.exists();
+
+    let mut log_file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(log_path)?;
+
+    if needs_header {
+        writeln!(
+            log_file,
+            "date,num_words,num_secs,elapsed_secs,wpm,accuracy,std_dev"
+        )?;
+    }
+
+    writeln!(
+        log_file,
+        "{},{},{},{:.2},{},{},{}",
+        result.date.format("%c"),
+        result.num_words,
+        result
+            .num_secs
+            .map_or(String::from(""), |ns| format!("{:.2}", ns)),
+        result.elapsed_secs,
+        result.wpm, // already rounded, no need to round to two decimal places
+        result.accuracy, // already rounded, no need to round to two decimal places
+        result
+            .std_dev
+            .map_or(String::from(""), |sd| format!("{:.2}", sd)),
+    )
+}
+
+/// appends a result to the json lines log as a single line
+pub fn append_json(log_path: &Path, result: &TestResult) -> io::Result<()> {
+    let mut log_file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(log_path)?;
+
+    writeln!(log_file, "{}", serde_json::to_string(result)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut thok =
+            Thok::new("ab cd".to_string(), 2, Some(30.), None, None, None);
+        "ab cx".chars().for_each(|c| thok.write(c));
+        thok.language = Some("english".to_string());
+        thok.seed = Some(42);
+
+        let result = TestResult::from_thok(&thok);
+        let json = serde_json::to_string(&result).unwrap();
+        assert!(!json.contains('\n'));
+        assert_eq!(serde_json::from_str::<TestResult>(&json).unwrap(), result);
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["language"], "english");
+        assert_eq!(value["keys"]["d"]["misses"], 1);
+        // iso-8601, e.g. 2022-03-01T12:00:00.000+01:00
+        assert!(
+            DateTime::parse_from_rfc3339(value["date"].as_str().unwrap())
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_print_as() {
+        let mut result = TestResult::bare(Local::now());
+        result.wpm = 72.0;
+        result.accuracy = 96.0;
+        result.std_dev = Some(1.234);
+
+        assert_eq!(
+            result.print_as(PrintFormat::Text).unwrap(),
+            "72 wpm 96% acc 1.23 sd"
+        );
+        let json = result.print_as(PrintFormat::Json).unwrap();
+        assert_eq!(serde_json::from_str::<TestResult>(&json).unwrap(), result);
+    }
+
+    #[test]
+    fn test_load_history_from() {
+        let log_dir = std::env::temp_dir()
+            .join(format!("thokr-history-{}", std::process::id()));
+        fs::create_dir_all(&log_dir).unwrap();
+        let date = |secs| Local.timestamp_opt(secs, 0).unwrap();
+        // logged to csv alone, before the json log was switched on
+        let mut old = TestResult::bare(date(1_646_136_000));
+        old.wpm = 50.0;
+        append_csv(&log_dir.join("log.csv"), &old).unwrap();
+        // logged to both since
+        let mut csv = TestResult::bare(date(1_646_222_400));
+        csv.wpm = 60.0;
+        let mut json = TestResult::bare(date(1_646_222_400));
+        json.wpm = 70.0;
+        append_csv(&log_dir.join("log.csv"), &csv).unwrap();
+        append_json(&log_dir.join("log.jsonl"), &json).unwrap();
+
+        let wpm = |formats: &[LogFormat]| {
+            load_history_from(&log_dir, formats)
+                .unwrap()
+                .iter()
+                .map(|r| r.wpm)
+                .collect::<Vec<f64>>()
+        };
+        // the older csv history is kept, and the json copy of the shared test
+        // is preferred
+        assert_eq!(wpm(&[LogFormat::Csv, LogFormat::Json]), vec![50.0, 70.0]);
+        // a json log left over from before switching back to csv is stale
+        assert_eq!(wpm(&[LogFormat::Csv]), vec![50.0, 60.0]);
+        // there's no database yet, so only the other logs are read
+        assert_eq!(wpm(&[LogFormat::Sqlite, LogFormat::Json]), vec![70.0]);
+        assert!(wpm(&[]).is_empty());
+        fs::remove_dir_all(&log_dir).unwrap();
+    }
+}
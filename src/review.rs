@@ -0,0 +1,241 @@
+use crate::lang::Language;
+use crate::results;
+use crate::thok::WordStats;
+use chrono::{prelude::*, Duration};
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, error::Error, fs};
+
+/// a clean word typed at this pace or faster counts as an easy recall
+const FAST_MS_PER_CHAR: f64 = 240.0;
+
+/// the review history of a single word, scheduled with SM-2
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Card {
+    pub ease: f64,
+    pub interval_days: f64,
+    /// reviews passed in a row
+    pub repetitions: u32,
+    pub due: DateTime<Local>,
+    pub reviews: usize,
+    pub errors: usize,
+    pub mean_ms_per_char: Option<f64>,
+}
+
+impl Card {
+    fn new(now: DateTime<Local>) -> Self {
+        Self {
+            ease: 2.5,
+            interval_days: 0.0,
+            repetitions: 0,
+            due: now,
+            reviews: 0,
+            errors: 0,
+            mean_ms_per_char: None,
+        }
+    }
+
+    /// schedules the next review after the word was typed with a recall
+    /// `quality` from 0 to 5. anything under 3 is a lapse and due again
+    /// straight away
+    fn review(&mut self, quality: u8, now: DateTime<Local>) {
+        if quality < 3 {
+            self.repetitions = 0;
+            self.interval_days = 0.0;
+        } else {
+            self.repetitions += 1;
+            self.interval_days = match self.repetitions {
+                1 => 1.0,
+                2 => 6.0,
+                _ => self.interval_days * self.ease,
+            };
+        }
+
+        let miss = (5 - quality) as f64;
+        self.ease = (self.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(1.3);
+        self.due =
+            now + Duration::seconds((self.interval_days * 86400.0) as i64);
+    }
+}
+
+/// every word that has come up in review, kept in `review.json` next to the
+/// logs
+pub type Deck = BTreeMap<String, Card>;
+
+/// how well a word was recalled, from 5 for clean and fast down to 1 for
+/// several mistakes
+fn quality(stats: &WordStats) -> u8 {
+    match (stats.errors, stats.ms_per_char) {
+        (0, Some(ms)) if ms <= FAST_MS_PER_CHAR => 5,
+        (0, Some(ms)) if ms > FAST_MS_PER_CHAR * 2.0 => 3,
+        (0, _) => 4,
+        (1, _) => 2,
+        _ => 1,
+    }
+}
+
+/// updates the deck with the words of a finished test
+pub fn record(deck: &mut Deck, words: &[WordStats], now: DateTime<Local>) {
+    for stats in words {
+        let card = deck
+            .entry(stats.word.to_lowercase())
+            .or_insert_with(|| Card::new(now));
+
+        card.reviews += 1;
+        card.errors += stats.errors;
+        if let Some(ms) = stats.ms_per_char {
+            card.mean_ms_per_char =
+                Some(card.mean_ms_per_char.map_or(ms, |mean| {
+                    mean + (ms - mean) / card.reviews as f64
+                }));
+        }
+        card.review(quality(stats), now);
+    }
+}
+
+/// picks `num` words, up to half of them the most overdue words of the deck
+/// and the rest words of `language` that haven't been reviewed yet
+pub fn pick(
+    deck: &Deck,
+    language: &Language,
+    num: usize,
+    seed: u64,
+    now: DateTime<Local>,
+) -> Vec<String> {
+    let rng = &mut ChaCha8Rng::seed_from_u64(seed);
+
+    let mut due = deck
+        .iter()
+        .filter(|(_, card)| card.due <= now)
+        .collect::<Vec<(&String, &Card)>>();
+    due.sort_by_key(|(_, card)| card.due);
+
+    let mut words = due
+        .into_iter()
+        .take(num.div_ceil(2))
+        .map(|(word, _)| word.clone())
+        .collect::<Vec<String>>();
+
+    let mut new = language
+        .words()
+        .iter()
+        .filter(|word| !deck.contains_key(*word))
+        .collect::<Vec<&String>>();
+    // once every word has been seen, fill up with any of them
+    if new.is_empty() {
+        new = language.words().iter().collect();
+    }
+    words.extend(
+        new.choose_multiple(rng, num - words.len())
+            .map(|word| word.to_string()),
+    );
+
+    words.shuffle(rng);
+    words
+}
+
+pub fn load_deck() -> Result<Deck, Box<dyn Error>> {
+    let Some(path) = results::log_dir().map(|dir| dir.join("review.json"))
+    else {
+        return Ok(Deck::new());
+    };
+    if !path.exists() {
+        return Ok(Deck::new());
+    }
+
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+pub fn save_deck(deck: &Deck) -> Result<(), Box<dyn Error>> {
+    let Some(dir) = results::log_dir() else {
+        return Ok(());
+    };
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join("review.json"), serde_json::to_string_pretty(deck)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thok::Thok;
+
+    fn stats(word: &str, errors: usize, ms_per_char: f64) -> WordStats {
+        WordStats {
+            word: word.to_string(),
+            errors,
+            ms_per_char: Some(ms_per_char),
+        }
+    }
+
+    #[test]
+    fn test_record_corrected() {
+        let mut thok = Thok::new("ab cd".into(), 2, None, None, None, None);
+        "ax".chars().for_each(|c| thok.write(c));
+        thok.backspace();
+        "b cd".chars().for_each(|c| thok.write(c));
+
+        let now = Local::now();
+        let mut deck = Deck::new();
+        record(&mut deck, &thok.word_stats(), now);
+        // a word fixed along the way wasn't recalled cleanly
+        assert_eq!(deck["ab"].errors, 1);
+        assert_eq!(deck["ab"].due, now);
+        assert_eq!(deck["cd"].errors, 0);
+    }
+
+    #[test]
+    fn test_record() {
+        let now = Local::now();
+        let mut deck = Deck::new();
+
+        record(
+            &mut deck,
+            &[stats("the", 0, 100.), stats("of", 2, 300.)],
+            now,
+        );
+        assert_eq!(deck["the"].interval_days, 1.0);
+        assert_eq!(deck["the"].due, now + Duration::days(1));
+        assert_eq!(deck["of"].due, now);
+        assert!((deck["of"].ease - 1.96).abs() < 1e-9);
+
+        record(&mut deck, &[stats("the", 0, 200.)], now);
+        assert_eq!(deck["the"].interval_days, 6.0);
+        assert_eq!(deck["the"].reviews, 2);
+        assert_eq!(deck["the"].mean_ms_per_char, Some(150.0));
+
+        record(&mut deck, &[stats("the", 1, 200.)], now);
+        assert_eq!(deck["the"].repetitions, 0);
+        assert_eq!(deck["the"].due, now);
+    }
+
+    #[test]
+    fn test_pick() {
+        let now = Local::now();
+        let language = Language::new("english".to_string());
+        let mut deck = Deck::new();
+        record(
+            &mut deck,
+            &[
+                stats("the", 2, 100.),
+                stats("of", 3, 100.),
+                stats("and", 0, 100.),
+            ],
+            now,
+        );
+
+        let words = pick(&deck, &language, 6, 1, now);
+        assert_eq!(words.len(), 6);
+        assert!(words.contains(&"the".to_string()));
+        assert!(words.contains(&"of".to_string()));
+        // not due for another day
+        assert!(!words.contains(&"and".to_string()));
+        assert_eq!(words.iter().filter(|w| deck.contains_key(*w)).count(), 2);
+
+        // due words never take more than half the test
+        let words = pick(&deck, &language, 2, 1, now);
+        assert_eq!(words.iter().filter(|w| deck.contains_key(*w)).count(), 1);
+    }
+}
@@ -0,0 +1,359 @@
+use crate::keys::{Action, Context, KeyBinding, Keymap};
+use crate::results::{PrintFormat, TestResult};
+use crate::thok::{StrictMode, Thok};
+use crate::TICK_RATE_MS;
+use clap::Args;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    error::Error,
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+/// feed timed keystrokes through a test and print its results, without a
+/// terminal
+#[derive(Args, Debug, Clone)]
+pub struct SimulateArgs {
+    /// json file of keystrokes, either `{"prompt": "...", "keys": [{"ms": 0,
+    /// "key": "a"}, ...]}` with keys named as in the `[keys]` config, or a
+    /// result from `log.jsonl` or `--print-results json` to replay
+    #[clap(long)]
+    keys: PathBuf,
+
+    /// number of seconds the test is limited to. a replay keeps its own
+    #[clap(short = 's', long)]
+    secs: Option<f64>,
+
+    /// hold the caret back on mistakes. a replay keeps its own
+    #[clap(long, arg_enum)]
+    strict: Option<StrictMode>,
+
+    /// how the results are printed
+    #[clap(long, arg_enum, default_value = "text")]
+    format: PrintFormat,
+}
+
+/// a key pressed some milliseconds after the first one
+#[derive(Deserialize, Debug, Clone, Copy)]
+struct ScriptKey {
+    ms: u64,
+    key: KeyBinding,
+}
+
+#[derive(Deserialize, Debug)]
+struct Script {
+    prompt: String,
+    keys: Vec<ScriptKey>,
+}
+
+pub fn run(args: &SimulateArgs) -> Result<(), Box<dyn Error>> {
+    let json: Value = serde_json::from_str(&fs::read_to_string(&args.keys)?)?;
+
+    // every logged result is dated, a script never is
+    let (thok, logged) = if json.get("date").is_some() {
+        let logged: TestResult = serde_json::from_value(json)?;
+        let (thok, rebuilt) = replay(
+            &logged,
+            args.secs.or(logged.num_secs),
+            args.strict.or(logged.strict),
+        );
+        if rebuilt {
+            eprintln!(
+                "no prompt was logged, so it is rebuilt from the keystrokes"
+            );
+        }
+        (thok, Some(logged))
+    } else {
+        let script: Script = serde_json::from_value(json)?;
+        let keymap = Keymap::default();
+        // the tui would type the letter of a key held with ctrl or alt that
+        // isn't bound to anything, which is never what a script means
+        if let Some(key) = script.keys.iter().find(|k| {
+            let event = KeyEvent::from(k.key);
+            matches!(event.code, KeyCode::Char(_))
+                && event
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+                && keymap.action(&event, Context::Typing).is_none()
+        }) {
+            return Err(format!(
+                "`{}` at {} ms isn't bound to anything",
+                key.key, key.ms
+            )
+            .into());
+        }
+        let keys = script
+            .keys
+            .into_iter()
+            .map(|k| (k.ms, k.key.into()))
+            .collect::<Vec<(u64, KeyEvent)>>();
+        (simulate(script.prompt, &keys, args.secs, args.strict), None)
+    };
+    if !thok.has_started() {
+        return Err("no keys were typed".into());
+    }
+
+    println!("{}", TestResult::from_thok(&thok).print_as(args.format)?);
+    if let (Some(logged), PrintFormat::Text) = (logged, args.format) {
+        println!("logged: {}", logged.print_as(PrintFormat::Text)?);
+    }
+
+    Ok(())
+}
+
+/// types the keystrokes of a logged result into a test of its prompt, along
+/// with whether the prompt had to be rebuilt from the keystrokes. a log only
+/// keeps the keystrokes still standing at the end, so anything backspaced
+/// over is left out of a replay
+pub fn replay<'a>(
+    logged: &TestResult,
+    secs: Option<f64>,
+    strict: Option<StrictMode>,
+) -> (Thok<'a>, bool) {
+    // results logged before the prompt was are missing any skipped words
+    let rebuilt = logged.prompt.is_none();
+    let prompt = logged.prompt.clone().unwrap_or_else(|| {
+        logged.keystrokes.iter().map(|k| k.expected).collect()
+    });
+    let keys = logged
+        .keystrokes
+        .iter()
+        .map(|k| {
+            let key = KeyEvent {
+                code: KeyCode::Char(k.typed),
+                modifiers: KeyModifiers::NONE,
+            };
+            (k.ms, key)
+        })
+        .collect::<Vec<(u64, KeyEvent)>>();
+
+    (simulate(prompt, &keys, secs, strict), rebuilt)
+}
+
+/// types `keys` into a test of `prompt` the way the tui would, ticking the
+/// timer in between, and scores whatever was typed once the keys run out.
+/// keys are looked up in the default keymap, so deleting a word and
+/// restarting work as they do while typing. the actions that leave the test
+/// are ignored
+pub fn simulate<'a>(
+    prompt: String,
+    keys: &[(u64, KeyEvent)],
+    secs: Option<f64>,
+    strict: Option<StrictMode>,
+) -> Thok<'a> {
+    let number_of_words = prompt.split_whitespace().count();
+    let new_thok = || {
+        let mut thok = Thok::new(
+            prompt.clone(),
+            number_of_words,
+            secs,
+            None,
+            None,
+            strict,
+        );
+        thok.log_formats = vec![];
+        thok
+    };
+    let mut thok = new_thok();
+    let keymap = Keymap::default();
+    let start = SystemTime::UNIX_EPOCH;
+    let tick = Duration::from_millis(TICK_RATE_MS);
+    let mut ticks = 0;
+
+    for &(ms, key) in keys {
+        let at = start + Duration::from_millis(ms);
+        // the timer only runs once the first key has started the test
+        while let Some(started_at) = thok.started_at {
+            let next = started_at + tick * (ticks + 1);
+            if next > at || thok.has_finished() {
+                break;
+            }
+            ticks += 1;
+            thok.clock = Some(next);
+            thok.on_tick();
+        }
+        if thok.has_finished() {
+            break;
+        }
+
+        thok.clock = Some(at);
+        match keymap.action(&key, Context::Typing) {
+            Some(Action::WordDelete) => thok.word_backspace(),
+            Some(Action::Restart) => {
+                thok = new_thok();
+                ticks = 0;
+            }
+            Some(_) => {}
+            None => match key.code {
+                KeyCode::Backspace => thok.backspace(),
+                KeyCode::Char(c) => thok.write(c),
+                _ => {}
+            },
+        }
+        if thok.has_finished() {
+            break;
+        }
+    }
+
+    // a timed test runs out its clock after the last key
+    while let Some(started_at) = thok.started_at {
+        if thok.has_finished() || thok.seconds_remaining.is_none() {
+            break;
+        }
+        ticks += 1;
+        thok.clock = Some(started_at + tick * ticks);
+        thok.on_tick();
+    }
+
+    if thok.has_started() && !thok.fatal_error() {
+        thok.calc_results();
+    }
+
+    thok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thok::Outcome;
+
+    /// keys pressed `ms_apart` from one another
+    fn keys(names: &[&str], ms_apart: u64) -> Vec<(u64, KeyEvent)> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let key = name.parse::<KeyBinding>().unwrap();
+                (i as u64 * ms_apart, key.into())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_simulate() {
+        let typed = keys(&["a", "x", "backspace", "b", "space", "c", "d"], 100);
+        let thok = simulate("ab cd".to_string(), &typed, None, None);
+        assert!(thok.has_finished());
+        assert_eq!(thok.elapsed(), Some(Duration::from_millis(600)));
+        assert!(thok.input.iter().all(|i| i.outcome == Outcome::Correct));
+        assert_eq!(thok.mistakes, 1);
+
+        // the same keys always score the same
+        let again = simulate("ab cd".to_string(), &typed, None, None);
+        assert_eq!((again.wpm, again.accuracy), (thok.wpm, thok.accuracy));
+
+        // keys bound to actions act as they do in the tui
+        let typed = keys(
+            &[
+                "x", "ctrl+r", "a", "b", "space", "c", "x", "ctrl+h", "c", "d",
+            ],
+            100,
+        );
+        let thok = simulate("ab cd".to_string(), &typed, None, None);
+        assert!(thok.has_finished());
+        assert_eq!(
+            thok.started_at,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_millis(200))
+        );
+        assert_eq!(thok.mistakes, 1);
+    }
+
+    #[test]
+    fn test_simulate_timed() {
+        let typed = keys(&["a", "b", "space", "c"], 100);
+        let thok = simulate("ab cd".to_string(), &typed, Some(1.0), None);
+        assert!(thok.has_finished());
+        assert_eq!(thok.input.len(), 4);
+        // the timer keeps ticking for the second after the first key
+        let elapsed = thok.elapsed().unwrap();
+        assert!(elapsed >= Duration::from_secs(1));
+        assert!(elapsed <= Duration::from_millis(1100));
+    }
+
+    #[test]
+    fn test_replay() {
+        // `cd` is skipped and `x` runs past the end of `ef`
+        let typed = keys(
+            &["a", "b", "space", "c", "space", "e", "f", "x", "space", "g"],
+            100,
+        );
+        let thok = simulate("ab cde ef g".to_string(), &typed, None, None);
+        assert!(thok.has_finished());
+        assert!(thok.input.iter().any(|i| i.outcome == Outcome::Skipped));
+        assert_eq!(thok.overflow[&9].len(), 1);
+        let logged = TestResult::from_thok(&thok);
+        assert_eq!(logged.keystrokes.len(), typed.len());
+
+        let (replayed, rebuilt) = replay(&logged, None, None);
+        assert!(!rebuilt);
+        assert_eq!(replayed.prompt, thok.prompt);
+        assert_eq!(
+            (replayed.wpm, replayed.accuracy, replayed.mistakes),
+            (thok.wpm, thok.accuracy, thok.mistakes)
+        );
+
+        // older logs have no prompt, so it's rebuilt without the skipped word
+        let mut unprompted = logged.clone();
+        unprompted.prompt = None;
+        let (replayed, rebuilt) = replay(&unprompted, None, None);
+        assert!(rebuilt);
+        assert_ne!(replayed.prompt, thok.prompt);
+
+        // the space that skips the rest of the last word ends the test
+        let typed = keys(&["t", "h", "e", "space", "f", "space"], 100);
+        let thok = simulate("the fox".to_string(), &typed, None, None);
+        assert!(thok.has_finished());
+        let logged = TestResult::from_thok(&thok);
+        assert_eq!(logged.keystrokes.len(), typed.len());
+        assert_eq!(logged.keystrokes[5].expected, 'o');
+
+        let (replayed, _) = replay(&logged, None, None);
+        assert!(replayed.has_finished());
+        assert_eq!(replayed.elapsed(), thok.elapsed());
+        assert_eq!(
+            (replayed.wpm, replayed.accuracy, replayed.mistakes),
+            (thok.wpm, thok.accuracy, thok.mistakes)
+        );
+    }
+
+    /// the error from running `script`, written to a file named after `name`
+    fn run_error(name: &str, script: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "thokr-{}-{}.json",
+            name,
+            std::process::id()
+        ));
+        fs::write(&path, script).unwrap();
+        let args = SimulateArgs {
+            keys: path.clone(),
+            secs: None,
+            strict: None,
+            format: PrintFormat::Text,
+        };
+        let err = run(&args).unwrap_err().to_string();
+        fs::remove_file(path).unwrap();
+        err
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = run_error(
+            "parse-error",
+            r#"{"prompt": "a", "keys": [{"ms": 0, "key": "ctrl+"}]}"#,
+        );
+        assert!(err.contains("no key in `ctrl+`"), "{}", err);
+    }
+
+    #[test]
+    fn test_unbound_modifier() {
+        let err = run_error(
+            "unbound-modifier",
+            r#"{"prompt": "ab", "keys": [{"ms": 0, "key": "a"},
+            {"ms": 100, "key": "alt+b"}]}"#,
+        );
+        assert_eq!(err, "`alt+b` at 100 ms isn't bound to anything");
+    }
+}
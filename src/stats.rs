@@ -0,0 +1,350 @@
+use crate::fingers::{self, FingerUsage};
+use crate::layout::Layout;
+use crate::ngrams::{self, NgramStats};
+use crate::results::{self, LogFormat, TestResult};
+use crate::util::{mean, median, percentile, trend};
+use chrono::prelude::*;
+use clap::Args;
+use serde::Serialize;
+use std::error::Error;
+
+/// how many of the slowest bigrams and trigrams are listed
+const SLOWEST_NGRAMS: usize = 5;
+
+/// summarize logged results
+#[derive(Args, Debug, Clone)]
+pub struct StatsArgs {
+    /// only include tests taken on or after this date (YYYY-MM-DD)
+    #[clap(long, parse(try_from_str = parse_date))]
+    since: Option<NaiveDate>,
+
+    /// only include tests taken on or before this date (YYYY-MM-DD)
+    #[clap(long, parse(try_from_str = parse_date))]
+    until: Option<NaiveDate>,
+
+    /// only include tests with this number of words
+    #[clap(short = 'w', long)]
+    words: Option<usize>,
+
+    /// only include tests limited to this number of seconds
+    #[clap(short = 's', long)]
+    secs: Option<f64>,
+
+    /// only include tests drawn from this language
+    #[clap(short = 'l', long)]
+    language: Option<String>,
+
+    /// percentile to report alongside the mean and median
+    #[clap(long, default_value_t = 90.0)]
+    percentile: f64,
+
+    /// print json instead of a table
+    #[clap(long)]
+    json: bool,
+}
+
+fn parse_date(s: &str) -> Result<NaiveDate, chrono::ParseError> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct MetricSummary {
+    pub mean: f64,
+    pub median: f64,
+    pub best: f64,
+    pub percentile: f64,
+    /// average change from one test to the next
+    pub trend: Option<f64>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Summary {
+    pub runs: usize,
+    pub first: Option<DateTime<Local>>,
+    pub last: Option<DateTime<Local>>,
+    pub percentile: f64,
+    pub wpm: Option<MetricSummary>,
+    pub accuracy: Option<MetricSummary>,
+    pub std_dev: Option<MetricSummary>,
+    /// only tests logged with their keystrokes count towards this
+    pub fingers: Option<FingerUsage>,
+    pub slowest_bigrams: Vec<NgramStats>,
+    pub slowest_trigrams: Vec<NgramStats>,
+}
+
+impl StatsArgs {
+    fn matches(&self, result: &TestResult) -> bool {
+        let date = result.date.naive_local().date();
+
+        self.since.is_none_or(|since| date >= since)
+            && self.until.is_none_or(|until| date <= until)
+            && self.words.is_none_or(|words| result.num_words == words)
+            && self.secs.is_none_or(|secs| {
+                result.num_secs.is_some_and(|ns| (ns - secs).abs() < 0.5)
+            })
+            && self.language.as_ref().is_none_or(|language| {
+                result
+                    .language
+                    .as_ref()
+                    .is_some_and(|l| l.eq_ignore_ascii_case(language))
+            })
+    }
+}
+
+fn summarize_metric(
+    data: &[f64],
+    pct: f64,
+    lower_is_better: bool,
+) -> Option<MetricSummary> {
+    let best = if lower_is_better {
+        data.iter().copied().reduce(f64::min)?
+    } else {
+        data.iter().copied().reduce(f64::max)?
+    };
+
+    Some(MetricSummary {
+        mean: mean(data)?,
+        median: median(data)?,
+        best,
+        percentile: percentile(data, pct)?,
+        trend: trend(data),
+    })
+}
+
+pub fn summarize(
+    history: &[&TestResult],
+    pct: f64,
+    layout: &Layout,
+) -> Summary {
+    let metric = |f: fn(&TestResult) -> f64| {
+        history.iter().map(|r| f(r)).collect::<Vec<f64>>()
+    };
+    let keystrokes = || history.iter().map(|r| r.keystrokes.as_slice());
+    let slowest = |n| {
+        let mut stats = ngrams::latencies(keystrokes(), n);
+        stats.truncate(SLOWEST_NGRAMS);
+        stats
+    };
+
+    Summary {
+        runs: history.len(),
+        first: history.first().map(|r| r.date),
+        last: history.last().map(|r| r.date),
+        percentile: pct,
+        wpm: summarize_metric(&metric(|r| r.wpm), pct, false),
+        accuracy: summarize_metric(&metric(|r| r.accuracy), pct, false),
+        // a lower standard deviation means a steadier pace. tests imported
+        // without one are left out rather than counted as perfectly steady
+        std_dev: summarize_metric(
+            &history
+                .iter()
+                .filter_map(|r| r.std_dev)
+                .collect::<Vec<f64>>(),
+            pct,
+            true,
+        ),
+        fingers: history
+            .iter()
+            .any(|r| !r.keystrokes.is_empty())
+            .then(|| fingers::analyze(layout, keystrokes())),
+        slowest_bigrams: slowest(2),
+        slowest_trigrams: slowest(3),
+    }
+}
+
+fn format_table(summary: &Summary) -> String {
+    let mut table = format!("{} tests", summary.runs);
+    if let (Some(first), Some(last)) = (summary.first, summary.last) {
+        table += &format!(
+            " from {} to {}",
+            first.format("%Y-%m-%d"),
+            last.format("%Y-%m-%d")
+        );
+    }
+    table += "\n\n";
+    table += &format!(
+        "{:<10}{:>10}{:>10}{:>10}{:>10}{:>10}\n",
+        "",
+        "mean",
+        "median",
+        "best",
+        format!("p{}", summary.percentile),
+        "trend"
+    );
+
+    for (name, metric) in [
+        ("wpm", &summary.wpm),
+        ("accuracy", &summary.accuracy),
+        ("std_dev", &summary.std_dev),
+    ] {
+        if let Some(m) = metric {
+            table += &format!(
+                "{:<10}{:>10.2}{:>10.2}{:>10.2}{:>10.2}{:>10}\n",
+                name,
+                m.mean,
+                m.median,
+                m.best,
+                m.percentile,
+                m.trend.map_or(String::from("-"), |t| format!("{:+.2}", t))
+            );
+        }
+    }
+
+    if let Some(usage) = &summary.fingers {
+        table += &format!(
+            "\n{:<14}{:>10}{:>10}{:>10}\n",
+            "finger", "presses", "errors %", "mean ms"
+        );
+        for (finger, stats) in &usage.fingers {
+            table += &format!(
+                "{:<14}{:>10}{:>10.2}{:>10}\n",
+                finger.to_string(),
+                stats.presses,
+                stats.error_rate(),
+                stats
+                    .mean_ms
+                    .map_or(String::from("-"), |ms| format!("{:.0}", ms))
+            );
+        }
+
+        let percentage = |p: Option<f64>| {
+            p.map_or(String::from("-"), |p| format!("{:.2}%", p))
+        };
+        table += &format!(
+            "\nsame finger bigrams {}   hand alternation {}\n",
+            percentage(usage.same_finger_bigrams),
+            percentage(usage.hand_alternation)
+        );
+    }
+
+    for (name, slowest) in [
+        ("bigrams", &summary.slowest_bigrams),
+        ("trigrams", &summary.slowest_trigrams),
+    ] {
+        if !slowest.is_empty() {
+            table += &format!(
+                "\nslowest {:<6}{:>10}{:>10}\n",
+                name, "count", "mean ms"
+            );
+            for stats in slowest {
+                table += &format!(
+                    "{:<14}{:>10}{:>10.0}\n",
+                    stats.ngram, stats.count, stats.mean_ms
+                );
+            }
+        }
+    }
+
+    table
+}
+
+pub fn run(
+    args: &StatsArgs,
+    layout: &Layout,
+    log_formats: &[LogFormat],
+) -> Result<(), Box<dyn Error>> {
+    let history = results::load_history(log_formats)?;
+    let matching = history
+        .iter()
+        .filter(|r| args.matches(r))
+        .collect::<Vec<&TestResult>>();
+    let summary = summarize(&matching, args.percentile, layout);
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else if summary.runs == 0 {
+        println!("no logged tests match");
+    } else {
+        print!("{}", format_table(&summary));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thok::Thok;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[clap(flatten)]
+        args: StatsArgs,
+    }
+
+    fn result(date: &str, wpm: f64, std_dev: f64) -> TestResult {
+        let thok = Thok::new("a".to_string(), 15, None, None, None, None);
+        let mut result = TestResult::from_thok(&thok);
+        result.date = Local
+            .from_local_datetime(
+                &NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .unwrap()
+                    .and_hms_opt(12, 0, 0)
+                    .unwrap(),
+            )
+            .unwrap();
+        result.language = Some("english".to_string());
+        result.wpm = wpm;
+        result.accuracy = 100.;
+        result.std_dev = Some(std_dev);
+        result
+    }
+
+    #[test]
+    fn test_filters() {
+        let args = TestCli::parse_from([
+            "stats",
+            "--since",
+            "2022-03-02",
+            "-l",
+            "English",
+            "-w",
+            "15",
+        ])
+        .args;
+
+        assert!(!args.matches(&result("2022-03-01", 80., 1.)));
+        assert!(args.matches(&result("2022-03-02", 80., 1.)));
+
+        let mut other = result("2022-03-03", 80., 1.);
+        other.num_secs = Some(30.);
+        assert!(args.matches(&other));
+        other.language = None;
+        assert!(!args.matches(&other));
+    }
+
+    #[test]
+    fn test_summarize() {
+        let mut history = vec![
+            result("2022-03-01", 60., 3.),
+            result("2022-03-02", 70., 1.),
+            result("2022-03-03", 80., 2.),
+        ];
+        let layout = Layout::qwerty();
+        let summary =
+            summarize(&history.iter().collect::<Vec<_>>(), 50., &layout);
+
+        assert_eq!(summary.runs, 3);
+        let wpm = summary.wpm.unwrap();
+        assert_eq!(wpm.mean, 70.);
+        assert_eq!(wpm.median, 70.);
+        assert_eq!(wpm.best, 80.);
+        assert_eq!(wpm.trend, Some(10.));
+        assert_eq!(summary.std_dev.unwrap().best, 1.);
+
+        assert_eq!(summary.fingers, None);
+        assert!(summary.slowest_bigrams.is_empty());
+
+        assert_eq!(summarize(&[], 50., &layout).wpm, None);
+
+        // a test imported without a standard deviation is left out of it
+        let mut imported = result("2022-03-04", 70., 0.);
+        imported.std_dev = None;
+        history.push(imported);
+        let summary =
+            summarize(&history.iter().collect::<Vec<_>>(), 50., &layout);
+        let std_dev = summary.std_dev.unwrap();
+        assert_eq!((std_dev.best, std_dev.mean), (1., 2.));
+    }
+}
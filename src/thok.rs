@@ -1,18 +1,104 @@
-use crate::util::std_dev;
+use crate::fingers::{self, FingerUsage};
+use crate::keys::Keymap;
+use crate::layout::Layout;
+use crate::lessons::Lesson;
+use crate::results::{self, Keystroke, LogFormat, TestResult};
+use crate::util::{mean, std_dev};
 use crate::TICK_RATE_MS;
-use chrono::prelude::*;
-use directories::ProjectDirs;
+use clap::ArgEnum;
 use itertools::Itertools;
 use ratatui::text::Text;
-use std::cell::OnceCell;
-use std::fs::OpenOptions;
-use std::io::{self, Write};
-use std::{char, collections::HashMap, time::SystemTime};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+use std::{
+    char,
+    collections::{BTreeMap, HashMap},
+    time::{Duration, SystemTime},
+};
 
 #[derive(Clone, Debug, Copy, PartialEq)]
 pub enum Outcome {
     Correct,
     Incorrect,
+    /// passed over by a space typed before the end of the word. the input
+    /// holds the letter that was skipped
+    Skipped,
+}
+
+/// how strictly mistakes hold the user back
+#[derive(Clone, Copy, Debug, PartialEq, ArgEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StrictMode {
+    /// the caret only moves on once the correct key is pressed
+    Letter,
+    /// the space after a word is refused while the word contains errors
+    Word,
+}
+
+/// how the caret on the next character to type is drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, ArgEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaretStyle {
+    /// the next character is drawn reversed
+    Block,
+    /// the next character is underlined
+    #[default]
+    Underline,
+    /// a bar in front of the next character. cells can't be split, so this
+    /// is always the terminal's own cursor
+    Bar,
+    /// no caret at all
+    Off,
+}
+
+/// the rules a death mode run has to keep to. any rule left as `None` is not
+/// enforced
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeathRules {
+    /// number of mistakes that end the run
+    pub lives: Option<usize>,
+    /// lowest accuracy, in percent, the run may drop to
+    pub min_accuracy: Option<f64>,
+    /// lowest wpm over the last few seconds the run may drop to
+    pub min_wpm: Option<f64>,
+}
+
+/// which death mode rule ended a run
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeathCause {
+    OutOfLives,
+    LowAccuracy,
+    LowWpm,
+}
+
+/// a death mode run that ended early, along with how it was going until then
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Death {
+    pub cause: DeathCause,
+    pub wpm: f64,
+    pub accuracy: f64,
+    pub words_done: usize,
+    pub elapsed_secs: f64,
+}
+
+/// accuracy isn't judged until this many keys have been pressed, otherwise a
+/// single early mistake would be fatal
+const DEATH_GRACE_KEYSTROKES: usize = 10;
+
+/// letters that can be typed past the end of a word before keys are refused
+pub const MAX_OVERFLOW: usize = 10;
+
+/// window the rolling wpm is measured over
+pub const ROLLING_WPM_SECS: f64 = 5.0;
+
+/// how a single key of the prompt went over the course of a test
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyStats {
+    pub presses: usize,
+    pub misses: usize,
+    /// average time taken to reach the key from the previous one
+    pub mean_ms: Option<f64>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -22,6 +108,23 @@ pub struct Input {
     pub timestamp: SystemTime,
 }
 
+/// how a single word of the prompt was typed
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordStats {
+    pub word: String,
+    /// mistakes made in the word, including ones since corrected
+    pub errors: usize,
+    /// mean time per character, counted from the key before the word
+    pub ms_per_char: Option<f64>,
+}
+
+impl WordStats {
+    /// the pace the word was typed at, counting five characters as a word
+    pub fn wpm(&self) -> Option<f64> {
+        self.ms_per_char.map(|ms| 12000.0 / ms)
+    }
+}
+
 /// represents a test being displayed to the user
 pub struct Thok<'a> {
     pub prompt: String,
@@ -37,9 +140,57 @@ pub struct Thok<'a> {
     pub accuracy: f64,
     pub std_dev: f64,
     pub pace: Option<f64>,
-    pub death_mode: bool,
-    pub skull_cache: OnceCell<Text<'a>>,
+    pub death_mode: Option<DeathRules>,
+    pub death: Option<Death>,
+    /// every mistake made so far, including ones since backspaced
+    pub mistakes: usize,
+    /// `mistakes` split up by word, keyed by the index of the space after the
+    /// word, or the length of the prompt for the last one
+    pub word_mistakes: BTreeMap<usize, usize>,
+    pub strict: Option<StrictMode>,
+    /// keystrokes refused by a strict mode, which still count as mistakes
+    pub rejected: Vec<Input>,
+    /// letters typed past the end of a word, by the index of the space they
+    /// were typed in front of. they're kept out of `input` so the rest of the
+    /// test stays lined up with the prompt
+    pub overflow: BTreeMap<usize, Vec<Input>>,
+    /// whether the last keystroke was refused by a strict mode
+    pub blocked: bool,
+    /// the skull drawn on death, with the width and height it was drawn at
+    pub skull_cache: RefCell<Option<((u16, u16), Text<'a>)>>,
     pub tabbed: bool,
+    pub show_live_stats: bool,
+    /// draw the keyboard of `layout` under the prompt
+    pub show_keyboard: bool,
+    /// layout the test is typed on, to know which key and finger each
+    /// character belongs to
+    pub layout: Layout,
+    /// the curriculum lesson the prompt was drawn for
+    pub lesson: Option<Lesson>,
+    /// key bindings, to name the right keys in legends
+    pub keys: Keymap,
+    /// when the countdown before the test runs out. typing is ignored and
+    /// the timer held back until then
+    pub countdown: Option<SystemTime>,
+    pub caret_style: CaretStyle,
+    /// glide the caret towards the cursor on ticks instead of jumping
+    pub smooth_caret: bool,
+    /// where a smooth caret is drawn, trailing `cursor_pos`
+    pub smooth_pos: usize,
+    /// place the terminal's own cursor on the caret
+    pub terminal_cursor: bool,
+    /// the screen cell of the caret as of the last render, for the terminal
+    /// cursor
+    pub cursor_cell: Cell<Option<(u16, u16)>>,
+    /// mark mistakes with more than colour and leave out the skull art
+    pub accessible: bool,
+    /// stands in for the system clock while keystrokes are replayed
+    pub clock: Option<SystemTime>,
+    pub log_formats: Vec<LogFormat>,
+    /// seed the prompt's words were picked with, if they were picked at random
+    pub seed: Option<u64>,
+    /// word list the prompt was drawn from
+    pub language: Option<String>,
 }
 
 impl Thok<'_> {
@@ -48,7 +199,8 @@ impl Thok<'_> {
         number_of_words: usize,
         number_of_secs: Option<f64>,
         pace: Option<f64>,
-        death_mode: bool,
+        death_mode: Option<DeathRules>,
+        strict: Option<StrictMode>,
     ) -> Self {
         Self {
             prompt,
@@ -65,15 +217,73 @@ impl Thok<'_> {
             std_dev: 0.0,
             pace,
             death_mode,
-            skull_cache: OnceCell::new(),
+            death: None,
+            mistakes: 0,
+            word_mistakes: BTreeMap::new(),
+            strict,
+            rejected: vec![],
+            overflow: BTreeMap::new(),
+            blocked: false,
+            skull_cache: RefCell::new(None),
             tabbed: false,
+            show_live_stats: false,
+            show_keyboard: false,
+            layout: Layout::qwerty(),
+            lesson: None,
+            keys: Keymap::default(),
+            countdown: None,
+            caret_style: CaretStyle::default(),
+            smooth_caret: false,
+            smooth_pos: 0,
+            terminal_cursor: false,
+            cursor_cell: Cell::new(None),
+            accessible: false,
+            clock: None,
+            log_formats: vec![LogFormat::Csv],
+            seed: None,
+            language: None,
         }
     }
 
+    pub fn now(&self) -> SystemTime {
+        self.clock.unwrap_or_else(SystemTime::now)
+    }
+
+    /// time since the test started, if it has
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.now().duration_since(self.started_at?).ok()
+    }
+
     pub fn on_tick(&mut self) {
         if let Some(v) = self.seconds_remaining {
             self.seconds_remaining = Some(v - (TICK_RATE_MS as f64 / 1000_f64));
         }
+        self.move_caret();
+        self.check_death();
+    }
+
+    /// where the caret is drawn
+    pub fn caret(&self) -> usize {
+        if self.smooth_caret {
+            self.smooth_pos
+        } else {
+            self.cursor_pos
+        }
+    }
+
+    /// moves a smooth caret half of the way to the cursor, so it eases in
+    fn move_caret(&mut self) {
+        let step = self.cursor_pos.abs_diff(self.smooth_pos).div_ceil(2);
+        if self.smooth_pos < self.cursor_pos {
+            self.smooth_pos += step;
+        } else {
+            self.smooth_pos -= step;
+        }
+    }
+
+    /// whether the caret is the terminal's cursor rather than a styled cell
+    pub fn uses_terminal_cursor(&self) -> bool {
+        self.terminal_cursor || self.caret_style == CaretStyle::Bar
     }
 
     pub fn get_expected_char(&self, idx: usize) -> char {
@@ -100,8 +310,7 @@ impl Thok<'_> {
             .filter(|i| i.outcome == Outcome::Correct)
             .collect::<Vec<Input>>();
 
-        let elapsed_secs =
-            self.started_at.unwrap().elapsed().unwrap().as_millis() as f64;
+        let elapsed_secs = self.elapsed().unwrap().as_millis() as f64;
 
         let whole_second_limit = elapsed_secs.floor();
 
@@ -160,35 +369,274 @@ impl Thok<'_> {
             ))
         }
 
+        let mut word_start = 0;
         let correct_words = self
             .input
-            .clone()
             .split(|i| i.char == ' ')
-            .filter(|&s| !s.iter().any(|i| i.outcome == Outcome::Incorrect))
+            .filter(|word| {
+                let end = word_start + word.len();
+                word_start = end + 1;
+                word.iter().all(|i| i.outcome == Outcome::Correct)
+                    && !self.overflow.contains_key(&end)
+            })
             .count();
 
         self.wpm = (correct_words as f64
-            / (self.started_at.unwrap().elapsed().unwrap().as_secs_f64()
-                / 60.0))
+            / (self.elapsed().unwrap().as_secs_f64() / 60.0))
             .ceil();
-        self.accuracy =
-            ((correct_chars.len() as f64 / self.input.len() as f64) * 100.0)
-                .round();
+        self.accuracy = ((correct_chars.len() as f64 / self.attempts() as f64)
+            * 100.0)
+            .round();
 
         let _ = self.save_results();
     }
 
+    /// wpm so far, counting every five correct characters as a word
+    pub fn live_wpm(&self) -> f64 {
+        let elapsed_secs = match self.elapsed() {
+            // avoid wild numbers during the first keystrokes
+            Some(elapsed) => elapsed.as_secs_f64().max(1.0),
+            None => return 0.0,
+        };
+        let correct_chars = self
+            .input
+            .iter()
+            .filter(|i| i.outcome == Outcome::Correct)
+            .count();
+
+        ((correct_chars as f64 / 5.0) / (elapsed_secs / 60.0)).round()
+    }
+
+    /// accuracy so far, which is 100% until something has been typed
+    pub fn live_accuracy(&self) -> f64 {
+        if self.attempts() == 0 {
+            return 100.0;
+        }
+        let correct_chars = self
+            .input
+            .iter()
+            .filter(|i| i.outcome == Outcome::Correct)
+            .count();
+
+        ((correct_chars as f64 / self.attempts() as f64) * 100.0).round()
+    }
+
+    /// wpm over the last few seconds, used to judge the death mode wpm rule
+    pub fn rolling_wpm(&self) -> f64 {
+        let correct_chars = self
+            .input
+            .iter()
+            .filter(|i| i.outcome == Outcome::Correct)
+            .filter(|i| {
+                self.now()
+                    .duration_since(i.timestamp)
+                    .is_ok_and(|e| e.as_secs_f64() <= ROLLING_WPM_SECS)
+            })
+            .count();
+
+        ((correct_chars as f64 / 5.0) / (ROLLING_WPM_SECS / 60.0)).round()
+    }
+
+    /// mistakes left before death mode ends the run, if lives are in play
+    pub fn lives_left(&self) -> Option<usize> {
+        self.death_mode?
+            .lives
+            .map(|lives| lives.saturating_sub(self.mistakes))
+    }
+
+    /// ends the run if one of the death mode rules has been broken
+    fn check_death(&mut self) {
+        let Some(rules) = self.death_mode else {
+            return;
+        };
+        if self.death.is_some() {
+            return;
+        }
+        let elapsed_secs = match self.elapsed() {
+            Some(elapsed) => elapsed.as_secs_f64(),
+            None => return,
+        };
+
+        let cause = if self.lives_left() == Some(0) {
+            Some(DeathCause::OutOfLives)
+        } else if rules.min_accuracy.is_some_and(|min| {
+            self.keystrokes() >= DEATH_GRACE_KEYSTROKES
+                && self.live_accuracy() < min
+        }) {
+            Some(DeathCause::LowAccuracy)
+        } else if rules.min_wpm.is_some_and(|min| {
+            elapsed_secs >= ROLLING_WPM_SECS && self.rolling_wpm() < min
+        }) {
+            Some(DeathCause::LowWpm)
+        } else {
+            None
+        };
+
+        self.death = cause.map(|cause| Death {
+            cause,
+            wpm: self.live_wpm(),
+            accuracy: self.live_accuracy(),
+            words_done: self.words_done(),
+            elapsed_secs,
+        });
+    }
+
+    /// the most recent keystroke, whether it was accepted or refused
+    pub fn last_keystroke(&self) -> Option<&Input> {
+        self.input
+            .iter()
+            .filter(|input| input.outcome != Outcome::Skipped)
+            .chain(&self.rejected)
+            .chain(self.overflow.values().flatten())
+            .max_by_key(|input| input.timestamp)
+    }
+
+    /// every key that was pressed, including ones refused by a strict mode.
+    /// letters passed over by skipping to the next word never were, so they
+    /// only count as mistakes
+    fn keystrokes(&self) -> usize {
+        self.input
+            .iter()
+            .filter(|i| i.outcome != Outcome::Skipped)
+            .count()
+            + self.rejected.len()
+            + self.overflow.values().map(Vec::len).sum::<usize>()
+    }
+
+    /// what accuracy is out of: every keystroke along with every letter
+    /// skipped over, which were missed without being pressed
+    fn attempts(&self) -> usize {
+        self.keystrokes()
+            + self
+                .input
+                .iter()
+                .filter(|i| i.outcome == Outcome::Skipped)
+                .count()
+    }
+
+    /// number of words in the prompt whose trailing space has been typed
+    pub fn words_done(&self) -> usize {
+        self.prompt
+            .chars()
+            .take(self.input.len())
+            .filter(|c| *c == ' ')
+            .count()
+    }
+
+    pub fn total_words(&self) -> usize {
+        self.prompt.split_whitespace().count()
+    }
+
+    /// words of the prompt typed with at least one error, in the order they
+    /// appear and without duplicates. a wrong space counts against the word
+    /// before it
+    pub fn missed_words(&self) -> Vec<String> {
+        let mut missed: Vec<String> = vec![];
+        let mut word = String::new();
+        let mut has_error = false;
+
+        for (idx, c) in self.prompt.chars().chain([' ']).enumerate() {
+            has_error |= self
+                .input
+                .get(idx)
+                .is_some_and(|i| i.outcome != Outcome::Correct)
+                || (c == ' ' && self.overflow.contains_key(&idx));
+
+            if c == ' ' {
+                if has_error && !word.is_empty() && !missed.contains(&word) {
+                    missed.push(word.clone());
+                }
+                word.clear();
+                has_error = false;
+            } else {
+                word.push(c);
+            }
+        }
+
+        missed
+    }
+
+    /// stats for every word of the prompt typed in full, in prompt order
+    pub fn word_stats(&self) -> Vec<WordStats> {
+        let chars = self.prompt.chars().collect::<Vec<char>>();
+        let mut stats = vec![];
+        let mut start = 0;
+
+        for end in (0..=chars.len())
+            .filter(|&i| chars.get(i).is_none_or(|c| *c == ' '))
+        {
+            let range = start..end;
+            start = end + 1;
+            if range.is_empty() || end > self.input.len() {
+                continue;
+            }
+
+            let typed = &self.input[range.clone()];
+            // the first word has no key before it to time from
+            let from = range
+                .start
+                .checked_sub(1)
+                .map_or(&typed[0], |i| &self.input[i]);
+            let timed_chars = typed.len() - usize::from(range.start == 0);
+            // a word cut short by a space wasn't typed at any real pace
+            let skipped = typed.iter().any(|i| i.outcome == Outcome::Skipped);
+            let ms_per_char = typed.last().and_then(|last| {
+                let elapsed =
+                    last.timestamp.duration_since(from.timestamp).ok()?;
+                (timed_chars > 0 && !skipped).then(|| {
+                    elapsed.as_secs_f64() * 1000.0 / timed_chars as f64
+                })
+            });
+
+            let standing = typed
+                .iter()
+                .filter(|i| i.outcome != Outcome::Correct)
+                .count()
+                + self.overflow.get(&end).map_or(0, Vec::len);
+            stats.push(WordStats {
+                word: chars[range].iter().collect(),
+                errors: standing
+                    .max(self.word_mistakes.get(&end).copied().unwrap_or(0)),
+                ms_per_char,
+            });
+        }
+
+        stats
+    }
+
     pub fn backspace(&mut self) {
+        self.blocked = false;
+        if let Some(overflow) = self.overflow.get_mut(&self.input.len()) {
+            overflow.pop();
+            if overflow.is_empty() {
+                self.overflow.remove(&self.input.len());
+            }
+            return;
+        }
+
         if self.cursor_pos > 0 {
             self.input.remove(self.cursor_pos - 1);
             self.decrement_cursor();
+            // a space that skipped the end of a word takes the skipped
+            // letters with it
+            while self
+                .input
+                .last()
+                .is_some_and(|i| i.outcome == Outcome::Skipped)
+            {
+                self.input.remove(self.cursor_pos - 1);
+                self.decrement_cursor();
+            }
         }
     }
 
     pub fn word_backspace(&mut self) {
+        self.blocked = false;
+        self.overflow.remove(&self.input.len());
         if let Some(Input { char: ' ', .. }) = self.input.last() {
             self.input.remove(self.cursor_pos - 1);
             self.decrement_cursor();
+            self.overflow.remove(&self.input.len());
         }
         while self.input.last().is_some_and(|i| i.char != ' ') {
             self.input.remove(self.cursor_pos - 1);
@@ -196,31 +644,180 @@ impl Thok<'_> {
         }
     }
 
+    pub fn counting_down(&self) -> bool {
+        self.countdown_left().is_some()
+    }
+
+    /// whole seconds left on the countdown, rounded up
+    pub fn countdown_left(&self) -> Option<u64> {
+        let left = self.countdown?.duration_since(self.now()).ok()?;
+        Some(left.as_secs() + u64::from(left.subsec_nanos() > 0))
+    }
+
+    /// whether a mistake was made before the first word was finished
+    pub fn missed_first_word(&self) -> bool {
+        self.mistakes > 0 && self.words_done() == 0
+    }
+
     pub fn start(&mut self) {
-        self.started_at = Some(SystemTime::now());
+        self.started_at = Some(self.now());
     }
 
     pub fn write(&mut self, c: char) {
+        if self.counting_down() {
+            return;
+        }
+
         let idx = self.input.len();
+        if idx >= self.prompt.chars().count() {
+            // only reachable when word strict mode holds back the last word
+            self.blocked = true;
+            return;
+        }
+        let expected = self.get_expected_char(idx);
+        // a space at the start of a word has nothing to skip, so it's ignored
+        // and doesn't start the test either
+        if expected != ' '
+            && c == ' '
+            && self.strict.is_none()
+            && (idx == 0 || self.get_expected_char(idx - 1) == ' ')
+        {
+            return;
+        }
+
         if idx == 0 && self.started_at.is_none() {
             self.start();
         }
 
-        let outcome = if c == self.get_expected_char(idx) {
+        if self.strict != Some(StrictMode::Letter) {
+            if expected == ' ' && c != ' ' {
+                self.write_overflow(idx, c);
+                return;
+            }
+            if expected != ' ' && c == ' ' && self.strict.is_none() {
+                self.skip_word(idx);
+                return;
+            }
+        }
+
+        let outcome = if c == expected {
             Outcome::Correct
         } else {
             Outcome::Incorrect
         };
 
-        self.input.insert(
-            self.cursor_pos,
-            Input {
+        if outcome == Outcome::Incorrect {
+            self.miss(idx, 1);
+        }
+
+        let accepted = match self.strict {
+            Some(StrictMode::Letter) => outcome == Outcome::Correct,
+            Some(StrictMode::Word) => {
+                expected != ' ' || !self.current_word_has_error()
+            }
+            None => true,
+        };
+        self.blocked = !accepted;
+
+        if accepted {
+            self.input.insert(
+                self.cursor_pos,
+                Input {
+                    char: c,
+                    outcome,
+                    timestamp: self.now(),
+                },
+            );
+            self.increment_cursor();
+        } else {
+            if outcome == Outcome::Correct {
+                // a correct space refused because the word has errors
+                self.miss(idx, 1);
+            }
+            self.rejected.push(Input {
                 char: c,
-                outcome,
-                timestamp: SystemTime::now(),
-            },
-        );
-        self.increment_cursor();
+                outcome: Outcome::Incorrect,
+                timestamp: self.now(),
+            });
+        }
+
+        self.check_death();
+    }
+
+    /// keeps a letter typed where a space was expected to one side, so the
+    /// rest of the test doesn't shift into errors
+    fn write_overflow(&mut self, idx: usize, c: char) {
+        self.miss(idx, 1);
+        let input = Input {
+            char: c,
+            outcome: Outcome::Incorrect,
+            timestamp: self.now(),
+        };
+
+        let overflow = self.overflow.entry(idx).or_default();
+        self.blocked = overflow.len() >= MAX_OVERFLOW;
+        if self.blocked {
+            self.rejected.push(input);
+        } else {
+            overflow.push(input);
+        }
+
+        self.check_death();
+    }
+
+    /// jumps to the start of the next word after a space typed before the end
+    /// of the current one, marking the letters in between as skipped
+    fn skip_word(&mut self, idx: usize) {
+        let now = self.now();
+        let skipped = self
+            .prompt
+            .chars()
+            .skip(idx)
+            .take_while(|c| *c != ' ')
+            .collect::<Vec<char>>();
+        self.miss(idx, skipped.len());
+        self.input.extend(skipped.into_iter().map(|c| Input {
+            char: c,
+            outcome: Outcome::Skipped,
+            timestamp: now,
+        }));
+        // the last word has no space after it
+        if self.input.len() < self.prompt.chars().count() {
+            self.input.push(Input {
+                char: ' ',
+                outcome: Outcome::Correct,
+                timestamp: now,
+            });
+        }
+        self.cursor_pos = self.input.len();
+        self.blocked = false;
+
+        self.check_death();
+    }
+
+    /// counts `count` mistakes against the word the prompt is at at `idx`
+    fn miss(&mut self, idx: usize, count: usize) {
+        let word_end = self
+            .prompt
+            .chars()
+            .skip(idx)
+            .position(|c| c == ' ')
+            .map_or(self.prompt.chars().count(), |offset| idx + offset);
+
+        self.mistakes += count;
+        *self.word_mistakes.entry(word_end).or_default() += count;
+    }
+
+    /// whether anything typed since the last space in the prompt is wrong
+    fn current_word_has_error(&self) -> bool {
+        self.overflow.contains_key(&self.input.len())
+            || self
+                .input
+                .iter()
+                .enumerate()
+                .rev()
+                .take_while(|(idx, _)| self.get_expected_char(*idx) != ' ')
+                .any(|(_, i)| i.outcome != Outcome::Correct)
     }
 
     pub fn has_started(&self) -> bool {
@@ -228,7 +825,9 @@ impl Thok<'_> {
     }
 
     pub fn has_finished(&self) -> bool {
-        let finished_prompt = self.input.len() == self.prompt.len();
+        let finished_prompt = self.input.len() == self.prompt.chars().count()
+            && !(self.strict == Some(StrictMode::Word)
+                && self.current_word_has_error());
         let out_of_time = self.seconds_remaining.is_some()
             && self.seconds_remaining.unwrap() <= 0.0;
         let is_fatal_error = self.fatal_error();
@@ -236,51 +835,61 @@ impl Thok<'_> {
         finished_prompt || out_of_time || is_fatal_error
     }
 
-    pub fn save_results(&self) -> io::Result<()> {
-        if let Some(proj_dirs) = ProjectDirs::from("", "", "thokr") {
-            let config_dir = proj_dirs.config_dir();
-            let log_path = config_dir.join("log.csv");
-
-            std::fs::create_dir_all(config_dir)?;
+    /// presses, misses and timing for each key of the prompt that was typed,
+    /// where a key's timing is measured from the keystroke before it.
+    /// skipped letters were never pressed, so they're left out
+    pub fn key_stats(&self) -> BTreeMap<char, KeyStats> {
+        let mut timings: BTreeMap<char, Vec<f64>> = BTreeMap::new();
+        let mut stats: BTreeMap<char, KeyStats> = BTreeMap::new();
 
-            // If the config file doesn't exist, we need to emit a header
-            let needs_header = !log_path.exists();
-
-            let mut log_file = OpenOptions::new()
-                .append(true)
-                .create(true)
-                .open(log_path)?;
+        for (idx, (input, expected)) in
+            self.input.iter().zip(self.prompt.chars()).enumerate()
+        {
+            if input.outcome == Outcome::Skipped {
+                continue;
+            }
+            let key = stats.entry(expected).or_default();
+            key.presses += 1;
+            if input.outcome != Outcome::Correct {
+                key.misses += 1;
+            }
 
-            if needs_header {
-                writeln!(
-                    log_file,
-                    "date,num_words,num_secs,elapsed_secs,wpm,accuracy,std_dev"
-                )?;
+            // there's nothing to time from a skipped letter either
+            if let Some(elapsed) = idx
+                .checked_sub(1)
+                .map(|prev| &self.input[prev])
+                .filter(|prev| prev.outcome != Outcome::Skipped)
+                .and_then(|prev| {
+                    input.timestamp.duration_since(prev.timestamp).ok()
+                })
+            {
+                timings
+                    .entry(expected)
+                    .or_default()
+                    .push(elapsed.as_secs_f64() * 1000.0);
             }
+        }
 
-            let elapsed_secs =
-                self.started_at.unwrap().elapsed().unwrap().as_secs_f64();
-
-            writeln!(
-                log_file,
-                "{},{},{},{:.2},{},{},{:.2}",
-                Local::now().format("%c"),
-                self.number_of_words,
-                self.number_of_secs
-                    .map_or(String::from(""), |ns| format!("{:.2}", ns)),
-                elapsed_secs,
-                self.wpm, // already rounded, no need to round to two decimal places
-                self.accuracy, // already rounded, no need to round to two decimal places
-                self.std_dev,
-            )?;
+        for (key, times) in timings {
+            if let Some(key_stats) = stats.get_mut(&key) {
+                key_stats.mean_ms = mean(&times);
+            }
         }
 
-        Ok(())
+        stats
+    }
+
+    /// how each finger fared over the test, on the layout it was typed on
+    pub fn finger_usage(&self) -> FingerUsage {
+        fingers::analyze(&self.layout, [Keystroke::from_thok(self).as_slice()])
+    }
+
+    pub fn save_results(&self) -> Result<(), Box<dyn Error>> {
+        results::save(&[TestResult::from_thok(self)], &self.log_formats)
     }
 
     pub fn fatal_error(&self) -> bool {
-        self.death_mode
-            && self.input.iter().any(|i| i.outcome == Outcome::Incorrect)
+        self.death.is_some()
     }
 }
 
@@ -337,9 +946,32 @@ mod tests {
             accuracy: 0.,
             std_dev: 0.,
             pace: None,
-            death_mode: false,
-            skull_cache: OnceCell::new(),
+            death_mode: None,
+            death: None,
+            mistakes: 0,
+            word_mistakes: BTreeMap::new(),
+            strict: None,
+            rejected: Vec::new(),
+            overflow: BTreeMap::new(),
+            blocked: false,
+            skull_cache: RefCell::new(None),
             tabbed: false,
+            show_live_stats: false,
+            show_keyboard: false,
+            layout: Layout::qwerty(),
+            lesson: None,
+            keys: Keymap::default(),
+            countdown: None,
+            caret_style: CaretStyle::default(),
+            smooth_caret: false,
+            smooth_pos: 0,
+            terminal_cursor: false,
+            cursor_cell: Cell::new(None),
+            accessible: false,
+            clock: None,
+            log_formats: Vec::new(),
+            seed: None,
+            language: None,
         };
 
         thok.calc_results();
@@ -367,9 +999,32 @@ mod tests {
             accuracy: 0.,
             std_dev: 0.,
             pace: None,
-            death_mode: false,
-            skull_cache: OnceCell::new(),
+            death_mode: None,
+            death: None,
+            mistakes: 0,
+            word_mistakes: BTreeMap::new(),
+            strict: None,
+            rejected: Vec::new(),
+            overflow: BTreeMap::new(),
+            blocked: false,
+            skull_cache: RefCell::new(None),
             tabbed: false,
+            show_live_stats: false,
+            show_keyboard: false,
+            layout: Layout::qwerty(),
+            lesson: None,
+            keys: Keymap::default(),
+            countdown: None,
+            caret_style: CaretStyle::default(),
+            smooth_caret: false,
+            smooth_pos: 0,
+            terminal_cursor: false,
+            cursor_cell: Cell::new(None),
+            accessible: false,
+            clock: None,
+            log_formats: Vec::new(),
+            seed: None,
+            language: None,
         };
 
         thok.calc_results();
@@ -397,9 +1052,32 @@ mod tests {
             accuracy: 0.,
             std_dev: 0.,
             pace: None,
-            death_mode: false,
-            skull_cache: OnceCell::new(),
+            death_mode: None,
+            death: None,
+            mistakes: 0,
+            word_mistakes: BTreeMap::new(),
+            strict: None,
+            rejected: Vec::new(),
+            overflow: BTreeMap::new(),
+            blocked: false,
+            skull_cache: RefCell::new(None),
             tabbed: false,
+            show_live_stats: false,
+            show_keyboard: false,
+            layout: Layout::qwerty(),
+            lesson: None,
+            keys: Keymap::default(),
+            countdown: None,
+            caret_style: CaretStyle::default(),
+            smooth_caret: false,
+            smooth_pos: 0,
+            terminal_cursor: false,
+            cursor_cell: Cell::new(None),
+            accessible: false,
+            clock: None,
+            log_formats: Vec::new(),
+            seed: None,
+            language: None,
         };
 
         thok.word_backspace();
@@ -420,4 +1098,315 @@ mod tests {
             .collect::<String>();
         assert_eq!(input, "one two ");
     }
+    #[test]
+    fn test_live_stats() {
+        let mut thok =
+            Thok::new("one two three".to_string(), 3, None, None, None, None);
+        assert_eq!(thok.live_accuracy(), 100.);
+        assert_eq!(thok.live_wpm(), 0.);
+        assert_eq!(thok.words_done(), 0);
+        assert_eq!(thok.total_words(), 3);
+
+        "one twp".chars().for_each(|c| thok.write(c));
+        assert_within!(thok.live_accuracy(), 86., 1.);
+        assert_eq!(thok.words_done(), 1);
+
+        thok.write(' ');
+        assert_eq!(thok.words_done(), 2);
+        // seven correct chars within the first second
+        assert_eq!(thok.live_wpm(), ((7. / 5.) * 60.0_f64).round());
+    }
+    #[test]
+    fn test_missed_words() {
+        let mut thok = Thok::new(
+            "one two one three".to_string(),
+            4,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert!(thok.missed_words().is_empty());
+
+        // a letter past the end of a word is a miss too
+        "onx two- onx thr".chars().for_each(|c| thok.write(c));
+        assert_eq!(thok.missed_words(), vec!["one", "two"]);
+
+        "ez".chars().for_each(|c| thok.write(c));
+        assert_eq!(thok.missed_words(), vec!["one", "two", "three"]);
+    }
+
+    #[test]
+    fn test_word_stats() {
+        let mut thok =
+            Thok::new("a bc def".to_string(), 3, None, None, None, None);
+
+        "a bx d".chars().for_each(|c| thok.write(c));
+        let stats = thok.word_stats();
+        assert_eq!(
+            stats
+                .iter()
+                .map(|s| (s.word.as_str(), s.errors))
+                .collect::<Vec<_>>(),
+            vec![("a", 0), ("bc", 1)]
+        );
+        // a single key word at the very start has nothing to be timed from
+        assert_eq!(stats[0].ms_per_char, None);
+        assert!(stats[1].ms_per_char.is_some());
+        assert_eq!(stats[0].wpm(), None);
+        let steady = WordStats {
+            word: "bc".to_string(),
+            errors: 0,
+            ms_per_char: Some(240.0),
+        };
+        assert_eq!(steady.wpm(), Some(50.0));
+
+        "ef".chars().for_each(|c| thok.write(c));
+        assert_eq!(thok.word_stats()[2].word, "def");
+
+        // mistakes that were fixed still count against the word
+        let mut thok =
+            Thok::new("ab cd ef".to_string(), 3, None, None, None, None);
+        "ax".chars().for_each(|c| thok.write(c));
+        thok.backspace();
+        "b cd e".chars().for_each(|c| thok.write(c));
+        assert_eq!(
+            thok.word_stats()
+                .iter()
+                .map(|s| (s.word.as_str(), s.errors))
+                .collect::<Vec<_>>(),
+            vec![("ab", 1), ("cd", 0)]
+        );
+    }
+    #[test]
+    fn test_strict_letter() {
+        let mut thok = Thok::new(
+            "ab cd".to_string(),
+            2,
+            None,
+            None,
+            None,
+            Some(StrictMode::Letter),
+        );
+
+        "axb".chars().for_each(|c| thok.write(c));
+        assert_eq!(thok.input.len(), 2);
+        assert_eq!(thok.rejected.len(), 1);
+        assert!(!thok.blocked);
+
+        thok.write('x');
+        assert!(thok.blocked);
+        assert_eq!(thok.cursor_pos, 2);
+        assert_eq!(thok.live_accuracy(), 50.);
+
+        thok.death_mode = Some(DeathRules {
+            lives: Some(3),
+            ..Default::default()
+        });
+        thok.write('y');
+        assert!(thok.fatal_error());
+        assert_eq!(thok.death.unwrap().cause, DeathCause::OutOfLives);
+    }
+    #[test]
+    fn test_death_rules() {
+        use std::time::Duration;
+
+        let rules = DeathRules {
+            lives: Some(2),
+            ..Default::default()
+        };
+        let mut thok = Thok::new(
+            "one two three".to_string(),
+            3,
+            None,
+            None,
+            Some(rules),
+            None,
+        );
+
+        "onx".chars().for_each(|c| thok.write(c));
+        assert_eq!(thok.lives_left(), Some(1));
+        thok.backspace();
+        "e tw".chars().for_each(|c| thok.write(c));
+        assert!(!thok.fatal_error());
+        thok.write('x');
+        assert!(thok.has_finished());
+        let death = thok.death.unwrap();
+        assert_eq!(death.cause, DeathCause::OutOfLives);
+        assert_eq!(death.words_done, 1);
+
+        let rules = DeathRules {
+            min_accuracy: Some(90.),
+            ..Default::default()
+        };
+        let mut thok = Thok::new(
+            "one two three".to_string(),
+            3,
+            None,
+            None,
+            Some(rules),
+            None,
+        );
+
+        // a mistake early on gets some grace
+        "onx twx".chars().for_each(|c| thok.write(c));
+        assert!(!thok.fatal_error());
+        " th".chars().for_each(|c| thok.write(c));
+        assert_eq!(thok.death.unwrap().cause, DeathCause::LowAccuracy);
+
+        let rules = DeathRules {
+            min_wpm: Some(30.),
+            ..Default::default()
+        };
+        let mut thok = Thok::new(
+            "one two three".to_string(),
+            3,
+            None,
+            None,
+            Some(rules),
+            None,
+        );
+
+        "one".chars().for_each(|c| thok.write(c));
+        thok.on_tick();
+        assert!(!thok.fatal_error());
+        thok.started_at = Some(SystemTime::now() - Duration::from_secs(10));
+        thok.on_tick();
+        assert_eq!(thok.death.unwrap().cause, DeathCause::LowWpm);
+    }
+    #[test]
+    fn test_strict_word() {
+        let mut thok = Thok::new(
+            "ab cd".to_string(),
+            2,
+            None,
+            None,
+            None,
+            Some(StrictMode::Word),
+        );
+
+        "ax ".chars().for_each(|c| thok.write(c));
+        assert_eq!(thok.input.len(), 2);
+        assert!(thok.blocked);
+
+        thok.backspace();
+        "b cx".chars().for_each(|c| thok.write(c));
+        assert_eq!(thok.input.len(), 5);
+        assert!(!thok.has_finished());
+
+        thok.write('d');
+        assert!(thok.blocked);
+
+        thok.backspace();
+        thok.write('d');
+        assert!(thok.has_finished());
+
+        // a wrong letter where the space goes doesn't get past the word
+        let mut thok = Thok::new(
+            "ab cd".to_string(),
+            2,
+            None,
+            None,
+            None,
+            Some(StrictMode::Word),
+        );
+        "abx ".chars().for_each(|c| thok.write(c));
+        assert_eq!(thok.input.len(), 2);
+        assert!(thok.blocked);
+        thok.backspace();
+        thok.write(' ');
+        assert_eq!(thok.input.len(), 3);
+        assert_eq!(thok.input[2].outcome, Outcome::Correct);
+    }
+
+    #[test]
+    fn test_countdown() {
+        use std::time::Duration;
+
+        let mut thok =
+            Thok::new("ab cd".to_string(), 2, None, None, None, None);
+        thok.countdown = Some(SystemTime::now() + Duration::from_millis(2500));
+        assert_eq!(thok.countdown_left(), Some(3));
+
+        // keys are ignored until the countdown runs out
+        thok.write('a');
+        assert!(thok.input.is_empty());
+        assert!(!thok.has_started());
+
+        thok.countdown = Some(SystemTime::now() - Duration::from_secs(1));
+        assert!(!thok.counting_down());
+        thok.write('x');
+        assert!(thok.missed_first_word());
+
+        "b c".chars().for_each(|c| thok.write(c));
+        thok.mistakes = 0;
+        thok.write('x');
+        assert!(!thok.missed_first_word());
+    }
+
+    #[test]
+    fn test_smooth_caret() {
+        let mut thok =
+            Thok::new("abcdef".to_string(), 1, None, None, None, None);
+        thok.smooth_caret = true;
+
+        "abcde".chars().for_each(|c| thok.write(c));
+        assert_eq!(thok.caret(), 0);
+        thok.on_tick();
+        assert_eq!(thok.caret(), 3);
+        thok.on_tick();
+        thok.on_tick();
+        assert_eq!(thok.caret(), 5);
+
+        thok.word_backspace();
+        thok.on_tick();
+        assert_eq!(thok.caret(), 2);
+    }
+
+    #[test]
+    fn test_word_alignment() {
+        let mut thok =
+            Thok::new("one two three".to_string(), 3, None, None, None, None);
+
+        // a stray space before the first word doesn't start the test
+        thok.write(' ');
+        assert!(!thok.has_started());
+        assert!(thok.input.is_empty());
+
+        // a space part of the way through a word jumps to the next one
+        "on two".chars().for_each(|c| thok.write(c));
+        assert_eq!(thok.input[2].outcome, Outcome::Skipped);
+        assert_eq!(thok.input.len(), 7);
+        assert_eq!(thok.mistakes, 1);
+        assert_eq!(thok.missed_words(), vec!["one"]);
+        // the skipped letter counts against accuracy but was never pressed
+        assert_eq!(thok.live_accuracy(), 86.0);
+        assert_eq!(thok.key_stats()[&'o'].presses, 2);
+        assert!(!thok.key_stats().contains_key(&'e'));
+        // spaces at the start of a word are ignored
+        thok.write(' ');
+        thok.write(' ');
+        assert_eq!(thok.input.len(), 8);
+
+        // extra letters don't push the next word out of line
+        thok.backspace();
+        "xy three".chars().for_each(|c| thok.write(c));
+        assert_eq!(thok.overflow[&7].len(), 2);
+        assert!(thok.has_finished());
+        assert_eq!(thok.missed_words(), vec!["one", "two"]);
+        assert_eq!(thok.word_stats()[1].errors, 2);
+        assert_eq!(thok.keystrokes(), 14);
+
+        let mut thok =
+            Thok::new("one two".to_string(), 2, None, None, None, None);
+        "on ".chars().for_each(|c| thok.write(c));
+        // backspacing the space goes back to where the word was left
+        thok.backspace();
+        assert_eq!(thok.input.len(), 2);
+        "ex".chars().for_each(|c| thok.write(c));
+        thok.backspace();
+        assert!(thok.overflow.is_empty());
+        thok.word_backspace();
+        assert!(thok.input.is_empty());
+    }
 }
@@ -3,14 +3,32 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Axis, Chart, Dataset, GraphType, Paragraph, Widget, Wrap},
+    widgets::{
+        Axis, Block, Borders, Chart, Clear, Dataset, GraphType, Padding,
+        Paragraph, Sparkline, Widget, Wrap,
+    },
 };
-use unicode_width::UnicodeWidthStr;
+use std::{ops::Range, time::Duration};
+use unicode_width::UnicodeWidthChar;
 
-use crate::thok::{Outcome, Thok};
+use crate::keys::{Action, Keymap};
+use crate::layout::Finger;
+use crate::thok::{CaretStyle, DeathCause, DeathRules, Outcome, Thok};
 
 const HORIZONTAL_MARGIN: u16 = 5;
 const VERTICAL_MARGIN: u16 = 2;
+/// number of prompt lines shown at once while typing
+const VISIBLE_PROMPT_LINES: u16 = 3;
+/// narrowest prompt area we are willing to wrap the prompt into
+const MIN_PROMPT_WIDTH: usize = 10;
+/// lines below the skull explaining what ended a death mode run
+const DEATH_SUMMARY_LINES: u16 = 4;
+/// padding line, three letter rows and the space bar
+const KEYBOARD_LINES: u16 = 5;
+/// how many of the slowest words of a test are listed with its results
+const SLOWEST_WORDS: usize = 3;
+/// how long the key just pressed stays lit on the keyboard
+const KEY_FLASH: Duration = Duration::from_millis(200);
 
 const BOLD_STYLE: Style = Style::new().add_modifier(Modifier::BOLD);
 const GREEN_BOLD_STYLE: Style =
@@ -20,15 +38,180 @@ const RED_BOLD_STYLE: Style =
 const DIM_BOLD_STYLE: Style = Style::new()
     .add_modifier(Modifier::BOLD)
     .add_modifier(Modifier::DIM);
-const UNDERLINED_DIM_BOLD_STYLE: Style = Style::new()
+/// letters passed over by jumping to the next word
+const SKIPPED_STYLE: Style =
+    Style::new().add_modifier(Modifier::DIM).fg(Color::Red);
+/// letters typed past the end of a word
+const OVERFLOW_STYLE: Style = Style::new()
     .add_modifier(Modifier::BOLD)
-    .add_modifier(Modifier::DIM)
-    .add_modifier(Modifier::UNDERLINED);
+    .add_modifier(Modifier::CROSSED_OUT)
+    .fg(Color::Red);
 const ITALIC_STYLE: Style = Style::new().add_modifier(Modifier::ITALIC);
 const MAGENTA_STYLE: Style = Style::new().fg(Color::Magenta);
 
+/// a list of rows to pick from, with an optional value next to each one
+pub struct Menu<'a> {
+    pub title: &'a str,
+    pub rows: Vec<(String, Option<String>)>,
+    pub selected: usize,
+    pub legend: &'a str,
+}
+
+impl Widget for Menu<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let rows = self.rows.len() as u16;
+        // title, a blank line and the rows
+        if area.height < rows + 4 || area.width < HORIZONTAL_MARGIN * 2 + 1 {
+            render_too_small(area, buf);
+            return;
+        }
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .horizontal_margin(HORIZONTAL_MARGIN)
+            .constraints(
+                [
+                    Constraint::Fill(1),
+                    Constraint::Length(2),
+                    Constraint::Length(rows),
+                    Constraint::Fill(1),
+                    Constraint::Length(1),
+                ]
+                .as_ref(),
+            )
+            .split(area);
+
+        Paragraph::new(Span::styled(self.title, BOLD_STYLE))
+            .alignment(Alignment::Center)
+            .render(chunks[1], buf);
+
+        let label_width = self
+            .rows
+            .iter()
+            .map(|(label, _)| label.len())
+            .max()
+            .unwrap_or(0);
+        let value_width = self
+            .rows
+            .iter()
+            .filter_map(|(_, value)| value.as_ref().map(|v| v.chars().count()))
+            .max()
+            .unwrap_or(0);
+        let lines = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(idx, (label, value))| {
+                let selected = idx == self.selected;
+                let text = match value {
+                    Some(value) => format!(
+                        "{:>lw$}  {} {:<vw$} {}",
+                        label,
+                        if selected { "<" } else { " " },
+                        value,
+                        if selected { ">" } else { " " },
+                        lw = label_width,
+                        vw = value_width
+                    ),
+                    None => label.clone(),
+                };
+
+                Line::from(Span::styled(
+                    text,
+                    if selected {
+                        GREEN_BOLD_STYLE
+                    } else {
+                        DIM_BOLD_STYLE
+                    },
+                ))
+            })
+            .collect::<Vec<Line>>();
+
+        // the rows are left aligned in a centered box so the labels and
+        // values stay lined up
+        let width = (lines.iter().map(Line::width).max().unwrap_or(0) as u16)
+            .min(chunks[2].width);
+        let rows_area = Rect {
+            x: chunks[2].x + (chunks[2].width - width) / 2,
+            width,
+            ..chunks[2]
+        };
+        Paragraph::new(lines).render(rows_area, buf);
+
+        Paragraph::new(Span::styled(self.legend, ITALIC_STYLE))
+            .render(chunks[4], buf);
+    }
+}
+
+/// every action and the keys bound to it, drawn over the current screen
+pub struct Help<'a>(pub &'a Keymap);
+
+impl Help<'_> {
+    fn lines(bindings: &[(Action, String)]) -> Vec<Line<'static>> {
+        let key_width = bindings
+            .iter()
+            .map(|(_, keys)| keys.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        bindings
+            .iter()
+            .map(|(action, keys)| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:>w$}  ", keys, w = key_width),
+                        BOLD_STYLE,
+                    ),
+                    Span::raw(action.describe()),
+                ])
+            })
+            .collect()
+    }
+}
+
+impl Widget for Help<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // the lines plus the border and a space on either side
+        let width_of = |lines: &[Line]| {
+            lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 4
+        };
+        let mut lines =
+            Self::lines(&self.0.bindings().collect::<Vec<(Action, String)>>());
+        // the first key of each action leaves room for what it does
+        if width_of(&lines) > area.width {
+            lines = Self::lines(
+                &self
+                    .0
+                    .bindings()
+                    .map(|(action, _)| (action, self.0.key(action)))
+                    .collect::<Vec<(Action, String)>>(),
+            );
+        }
+
+        let width = width_of(&lines).min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let help_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        Clear.render(help_area, buf);
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .padding(Padding::horizontal(1))
+                    .title(Span::styled(" keys ", BOLD_STYLE)),
+            )
+            .render(help_area, buf);
+    }
+}
+
 impl Widget for &Thok<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        self.cursor_cell.set(None);
         if self.has_finished() {
             self.render_finished(area, buf);
         } else {
@@ -38,142 +221,172 @@ impl Widget for &Thok<'_> {
 }
 
 impl Thok<'_> {
+    /// `(key) label` for each action, using whatever keys are bound to them.
+    /// actions that don't fit in `width` are left off the end
+    fn legend(
+        &self,
+        actions: &[(Action, &str)],
+        width: u16,
+    ) -> Paragraph<'static> {
+        let mut legend = String::new();
+        for (action, label) in actions {
+            let entry = format!("({}) {}", self.keys.key(*action), label);
+            let separator = if legend.is_empty() { "" } else { " / " };
+            if legend.chars().count() + separator.len() + entry.chars().count()
+                > width as usize
+            {
+                break;
+            }
+            legend += separator;
+            legend += &entry;
+        }
+
+        Paragraph::new(Span::styled(legend, ITALIC_STYLE))
+    }
+
     fn render_not_finished(&self, area: Rect, buf: &mut Buffer) {
-        let max_chars_per_line = area.width - (HORIZONTAL_MARGIN * 2);
-        let mut prompt_occupied_lines =
-            ((self.prompt.width() as f64 / max_chars_per_line as f64).ceil()
-                + 1.0) as u16;
+        let max_chars_per_line =
+            area.width.saturating_sub(HORIZONTAL_MARGIN * 2) as usize;
+        let time_left_lines =
+            if self.number_of_secs.is_some() || self.counting_down() {
+                2
+            } else {
+                0
+            };
+        let live_stats_lines = if self.show_live_stats { 2 } else { 0 };
+        let death_hud_lines = if self.death_mode.is_some() { 2 } else { 0 };
+
+        if max_chars_per_line < MIN_PROMPT_WIDTH
+            || area.height
+                < VISIBLE_PROMPT_LINES
+                    + time_left_lines
+                    + death_hud_lines
+                    + live_stats_lines
+                    + 1
+        {
+            render_too_small(area, buf);
+            return;
+        }
+
+        let keyboard = if self.show_keyboard {
+            self.keyboard()
+        } else {
+            vec![]
+        };
+        let keyboard_fits = keyboard
+            .iter()
+            .all(|line| line.width() <= max_chars_per_line)
+            && area.height
+                > VISIBLE_PROMPT_LINES
+                    + time_left_lines
+                    + death_hud_lines
+                    + live_stats_lines
+                    + KEYBOARD_LINES;
+        // the keyboard is a nice to have, so it goes before the prompt does
+        let keyboard_lines = if self.show_keyboard && keyboard_fits {
+            KEYBOARD_LINES
+        } else {
+            0
+        };
 
-        let time_left_lines = if self.number_of_secs.is_some() { 2 } else { 0 };
+        let overflow_width = |idx: usize| {
+            self.overflow.get(&idx).map_or(0, |overflow| {
+                overflow.iter().map(|i| i.char.width().unwrap_or(0)).sum()
+            })
+        };
+        let lines =
+            wrap_prompt(&self.prompt, max_chars_per_line, overflow_width);
+        let visible = visible_lines(&lines, self.cursor_pos);
+        let prompt_occupied_lines = visible.len() as u16;
 
         let pace_position = self.pace.and_then(|p| {
-            let total_chars = self.prompt.len() as f64;
-            let progress = ((p / 60.0)
-                * self.started_at?.elapsed().ok()?.as_secs_f64())
+            let total_chars = self.prompt.chars().count() as f64;
+            let progress = ((p / 60.0) * self.elapsed()?.as_secs_f64())
                 / self.number_of_words as f64;
             Some((progress * total_chars).round() as usize)
         });
 
-        if self.prompt.width() <= max_chars_per_line as usize {
-            prompt_occupied_lines = 1;
-        }
-
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .horizontal_margin(HORIZONTAL_MARGIN)
             .constraints(
                 [
-                    Constraint::Length(
-                        ((area.height as f64 - prompt_occupied_lines as f64)
-                            / 2.0) as u16
-                            - 1,
-                    ),
+                    Constraint::Fill(1),
                     Constraint::Length(time_left_lines),
+                    Constraint::Length(death_hud_lines),
                     Constraint::Length(prompt_occupied_lines),
-                    Constraint::Length(
-                        ((area.height as f64 - prompt_occupied_lines as f64)
-                            / 2.0) as u16,
-                    ),
+                    Constraint::Length(live_stats_lines),
+                    Constraint::Length(keyboard_lines),
+                    Constraint::Fill(1),
                     Constraint::Length(1),
                 ]
                 .as_ref(),
             )
             .split(area);
 
-        let mut past_pace_caret = false;
-
-        let mut spans = self
-            .input
+        let chars = self.prompt.chars().collect::<Vec<char>>();
+        let text = visible
             .iter()
-            .enumerate()
-            .map(|(idx, input)| {
-                let expected = self.get_expected_char(idx).to_string();
-
-                let mut display_char = match input.outcome {
-                    Outcome::Incorrect => Span::styled(
-                        match expected.as_str() {
-                            " " => "·".to_owned(),
-                            _ => expected,
-                        },
-                        RED_BOLD_STYLE,
-                    ),
-                    Outcome::Correct => {
-                        Span::styled(expected, GREEN_BOLD_STYLE)
-                    }
-                };
-                if let Some(p) = pace_position {
-                    if p == idx {
-                        let prev_style = display_char.style;
-                        display_char =
-                            display_char.style(prev_style.bg(Color::White));
-                        past_pace_caret = true;
-                    }
-                }
-                display_char
+            .map(|range| {
+                Line::from(
+                    range
+                        .clone()
+                        .flat_map(|idx| {
+                            // letters typed past the end of a word go in
+                            // front of the space they were typed at
+                            let overflow = self
+                                .overflow
+                                .get(&idx)
+                                .into_iter()
+                                .flatten()
+                                .map(|i| {
+                                    Span::styled(
+                                        i.char.to_string(),
+                                        OVERFLOW_STYLE,
+                                    )
+                                });
+                            overflow.chain([self.styled_char(
+                                idx,
+                                chars[idx],
+                                pace_position,
+                            )])
+                        })
+                        .collect::<Vec<Span>>(),
+                )
             })
-            .collect::<Vec<Span>>();
+            .collect::<Vec<Line>>();
 
-        spans.push(Span::styled(
-            self.get_expected_char(self.cursor_pos).to_string(),
-            if let Some(p) = pace_position {
-                if p == self.cursor_pos {
-                    UNDERLINED_DIM_BOLD_STYLE.bg(Color::White)
-                } else {
-                    UNDERLINED_DIM_BOLD_STYLE
-                }
-            } else {
-                UNDERLINED_DIM_BOLD_STYLE
-            },
-        ));
-
-        let full_span = Span::styled(
-            self.prompt
-                .chars()
-                .skip(self.cursor_pos + 1)
-                .collect::<String>(),
-            DIM_BOLD_STYLE,
-        );
-        let next_idx = self.cursor_pos + 1;
-        let len = self.prompt.len();
-        let remaining = if let Some(v) = pace_position {
-            if (next_idx..len).contains(&v) {
-                vec![
-                    Span::styled(
-                        self.prompt[next_idx..v].to_string(),
-                        DIM_BOLD_STYLE,
-                    ),
-                    Span::styled(
-                        self.get_expected_char(v).to_string(),
-                        DIM_BOLD_STYLE.bg(Color::White),
-                    ),
-                    Span::styled(
-                        self.prompt[v + 1..len].to_string(),
-                        DIM_BOLD_STYLE,
-                    ),
-                ]
-            } else {
-                vec![full_span]
-            }
+        let widget = Paragraph::new(text).alignment(if lines.len() == 1 {
+            // when the prompt is small enough to fit on one line
+            // centering the text gives a nice zen feeling
+            Alignment::Center
         } else {
-            vec![full_span]
-        };
-        spans.extend(remaining);
+            Alignment::Left
+        });
 
-        let widget = Paragraph::new(Line::from(spans))
-            .alignment(if prompt_occupied_lines == 1 {
-                // when the prompt is small enough to fit on one line
-                // centering the text gives a nice zen feeling
-                Alignment::Center
-            } else {
-                Alignment::Left
-            })
-            .wrap(Wrap { trim: true });
+        widget.render(chunks[3], buf);
 
-        widget.render(chunks[2], buf);
+        if self.uses_terminal_cursor() {
+            self.cursor_cell.set(caret_cell(
+                &chars,
+                overflow_width,
+                visible,
+                self.caret(),
+                chunks[3],
+                lines.len() == 1,
+            ));
+        }
 
-        if self.seconds_remaining.is_some() {
+        if let Some(countdown) = self.countdown_left() {
+            Paragraph::new(Span::styled(
+                countdown.to_string(),
+                GREEN_BOLD_STYLE,
+            ))
+            .alignment(Alignment::Center)
+            .render(chunks[1], buf);
+        } else if let Some(seconds_remaining) = self.seconds_remaining {
             let timer = Paragraph::new(Span::styled(
-                format!("{:.1}", self.seconds_remaining.unwrap()),
+                format!("{:.1}", seconds_remaining),
                 DIM_BOLD_STYLE,
             ))
             .alignment(Alignment::Center);
@@ -181,28 +394,223 @@ impl Thok<'_> {
             timer.render(chunks[1], buf);
         }
 
-        let legend = if self.tabbed {
-            Paragraph::new(Span::styled(
-                "(r)etry / (n)ew / (esc)ape / (tab) return",
-                ITALIC_STYLE,
+        if let Some(rules) = self.death_mode {
+            let hud = Paragraph::new(Span::styled(
+                self.death_hud(rules),
+                RED_BOLD_STYLE,
             ))
+            .alignment(Alignment::Center);
+
+            hud.render(chunks[2], buf);
+        }
+
+        if self.show_live_stats {
+            let stats = Paragraph::new(vec![
+                // padding between the prompt and the stats
+                Line::default(),
+                Line::from(Span::styled(
+                    format!(
+                        "{} wpm   {}% acc   {}/{} words",
+                        self.live_wpm(),
+                        self.live_accuracy(),
+                        self.words_done(),
+                        self.total_words()
+                    ),
+                    DIM_BOLD_STYLE,
+                )),
+            ])
+            .alignment(Alignment::Center);
+
+            stats.render(chunks[4], buf);
+        }
+
+        if keyboard_lines > 0 {
+            Paragraph::new(keyboard)
+                .alignment(Alignment::Center)
+                .render(chunks[5], buf);
+        }
+
+        let width = chunks[7].width;
+        let press = format!(
+            "Press {} for options or {} to restart",
+            self.keys.key(Action::Options),
+            self.keys.key(Action::Restart)
+        );
+        let legend = if self.tabbed {
+            self.legend(
+                &[
+                    (Action::Retry, "retry"),
+                    (Action::Next, "new"),
+                    (Action::Menu, "menu"),
+                    (Action::Options, "return"),
+                    (Action::Help, "help"),
+                ],
+                width,
+            )
+        } else if press.chars().count() <= width as usize {
+            Paragraph::new(Span::styled(press, ITALIC_STYLE))
         } else {
-            Paragraph::new(Span::styled("Press tab for options", ITALIC_STYLE))
+            self.legend(
+                &[(Action::Options, "options"), (Action::Restart, "restart")],
+                width,
+            )
         };
 
-        legend.render(chunks[4], buf);
+        legend.render(chunks[7], buf);
+    }
+
+    /// the keys of the layout coloured by finger, with the next key to type
+    /// highlighted and the key just pressed flashing green or red
+    fn keyboard(&self) -> Vec<Line<'static>> {
+        let next = self.prompt.chars().nth(self.cursor_pos);
+        let flash = self.last_keystroke().filter(|input| {
+            self.now()
+                .duration_since(input.timestamp)
+                .is_ok_and(|elapsed| elapsed < KEY_FLASH)
+        });
+        let same_key = |a: char, b: char| {
+            a == b
+                || self
+                    .layout
+                    .position(a)
+                    .is_some_and(|pos| self.layout.position(b) == Some(pos))
+        };
+        let key_style = |key: char| match flash {
+            Some(input) if same_key(input.char, key) => {
+                let style =
+                    Style::new().fg(Color::Black).bg(match input.outcome {
+                        Outcome::Correct => Color::Green,
+                        Outcome::Incorrect | Outcome::Skipped => Color::Red,
+                    });
+                if self.accessible && input.outcome != Outcome::Correct {
+                    style.add_modifier(Modifier::CROSSED_OUT)
+                } else {
+                    style
+                }
+            }
+            _ => {
+                let style =
+                    Style::new().fg(finger_color(self.layout.finger(key)));
+                if next.is_some_and(|next| same_key(next, key)) {
+                    style.add_modifier(Modifier::REVERSED | Modifier::BOLD)
+                } else {
+                    style
+                }
+            }
+        };
+
+        let rows = self.layout.rows();
+        let width = rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| i + row.chars().count() * 3)
+            .max()
+            .unwrap_or(0);
+
+        let mut lines = vec![Line::default()];
+        for (i, row) in rows.iter().enumerate() {
+            // each row sits a little further right than the one above it
+            let mut spans = vec![Span::raw(" ".repeat(i))];
+            spans.extend(
+                row.chars().map(|key| {
+                    Span::styled(format!(" {} ", key), key_style(key))
+                }),
+            );
+            let padding = width - i - row.chars().count() * 3;
+            spans.push(Span::raw(" ".repeat(padding)));
+            lines.push(Line::from(spans));
+        }
+
+        // the space bar spans the bottom row from c to m on qwerty
+        let indent = rows.len().saturating_sub(1) + 3 * 3;
+        let bar_width = 5 * 3;
+        lines.push(Line::from(vec![
+            Span::raw(" ".repeat(indent)),
+            Span::styled("─".repeat(bar_width), key_style(' ')),
+            Span::raw(" ".repeat(width.saturating_sub(indent + bar_width))),
+        ]));
+
+        lines
+    }
+
+    /// styles a single prompt character based on what has been typed so far,
+    /// where the caret is and where the pace cursor is
+    fn styled_char(
+        &self,
+        idx: usize,
+        expected: char,
+        pace_position: Option<usize>,
+    ) -> Span<'static> {
+        let mut span = match self.input.get(idx) {
+            Some(input) if input.outcome == Outcome::Incorrect => Span::styled(
+                match expected {
+                    ' ' => "·".to_owned(),
+                    _ => expected.to_string(),
+                },
+                RED_BOLD_STYLE,
+            ),
+            Some(input) if input.outcome == Outcome::Skipped => {
+                Span::styled(expected.to_string(), SKIPPED_STYLE)
+            }
+            Some(_) => Span::styled(expected.to_string(), GREEN_BOLD_STYLE),
+            // a strict mode refused the last key
+            None if idx == self.cursor_pos && self.blocked => {
+                Span::styled(expected.to_string(), RED_BOLD_STYLE)
+            }
+            None => Span::styled(expected.to_string(), DIM_BOLD_STYLE),
+        };
+
+        if self.accessible {
+            // mistakes stand out without having to tell red from green
+            let marker = match self.input.get(idx).map(|i| i.outcome) {
+                Some(Outcome::Incorrect) => Modifier::REVERSED,
+                Some(Outcome::Skipped) => Modifier::UNDERLINED,
+                None if idx == self.cursor_pos && self.blocked => {
+                    Modifier::REVERSED
+                }
+                _ => Modifier::empty(),
+            };
+            span = span.patch_style(Style::new().add_modifier(marker));
+        }
+
+        if idx == self.caret() && !self.uses_terminal_cursor() {
+            span = match self.caret_style {
+                CaretStyle::Block => span
+                    .patch_style(Style::new().add_modifier(Modifier::REVERSED)),
+                CaretStyle::Underline => span.patch_style(
+                    Style::new().add_modifier(Modifier::UNDERLINED),
+                ),
+                CaretStyle::Bar | CaretStyle::Off => span,
+            };
+        }
+
+        if pace_position == Some(idx) {
+            let prev_style = span.style;
+            span.style(prev_style.bg(Color::White))
+        } else {
+            span
+        }
     }
 
     fn render_finished(&self, area: Rect, buf: &mut Buffer) {
-        let bad_death = self.death_mode
-            && self.input.iter().any(|i| i.outcome == Outcome::Incorrect);
-        if bad_death {
+        if self.fatal_error() {
             self.render_finished_dead(area, buf);
         } else {
             self.render_finished_norm(area, buf);
         }
     }
     fn render_finished_norm(&self, area: Rect, buf: &mut Buffer) {
+        let missed_words = self.missed_words();
+        let missed_words_lines = if missed_words.is_empty() { 0 } else { 2 };
+        let word_speeds = self.word_speeds();
+        let word_lines = if word_speeds.is_empty() { 0 } else { 2 };
+        let lesson = self.lesson_summary();
+        let lesson_lines = if lesson.is_some() { 1 } else { 0 };
+        let fingers = self.fingers_summary();
+        let fingers_lines = (fingers.chars().count() as u16)
+            .div_ceil(area.width.saturating_sub(HORIZONTAL_MARGIN * 2).max(1))
+            .min(2);
+
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .horizontal_margin(HORIZONTAL_MARGIN)
@@ -211,6 +619,10 @@ impl Thok<'_> {
                 [
                     Constraint::Min(1),
                     Constraint::Length(1),
+                    Constraint::Length(word_lines),
+                    Constraint::Length(fingers_lines),
+                    Constraint::Length(lesson_lines),
+                    Constraint::Length(missed_words_lines),
                     Constraint::Length(1), // for padding
                     Constraint::Length(1),
                 ]
@@ -281,16 +693,242 @@ impl Thok<'_> {
 
         stats.render(chunks[1], buf);
 
-        let legend = Paragraph::new(Span::styled(
-            "(r)etry / (n)ew / (esc)ape",
-            ITALIC_STYLE,
-        ));
+        if !word_speeds.is_empty() {
+            self.render_word_speeds(&word_speeds, chunks[2], buf);
+        }
 
-        legend.render(chunks[3], buf);
+        if !fingers.is_empty() {
+            Paragraph::new(Span::styled(fingers, DIM_BOLD_STYLE))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true })
+                .render(chunks[3], buf);
+        }
+
+        if let Some(lesson) = lesson {
+            Paragraph::new(lesson)
+                .alignment(Alignment::Center)
+                .render(chunks[4], buf);
+        }
+
+        if !missed_words.is_empty() {
+            let missed = Paragraph::new(vec![
+                Line::default(),
+                Line::from(vec![
+                    Span::styled("missed: ", ITALIC_STYLE),
+                    Span::styled(missed_words.join(" "), RED_BOLD_STYLE),
+                ]),
+            ])
+            .alignment(Alignment::Center);
+
+            missed.render(chunks[5], buf);
+        }
+
+        let legend = if missed_words.is_empty() {
+            self.legend(
+                &[
+                    (Action::Retry, "retry"),
+                    (Action::Next, "new"),
+                    (Action::Menu, "menu"),
+                ],
+                chunks[7].width,
+            )
+        } else {
+            self.legend(
+                &[
+                    (Action::Retry, "retry"),
+                    (Action::Missed, "missed words"),
+                    (Action::Next, "new"),
+                    (Action::Menu, "menu"),
+                ],
+                chunks[7].width,
+            )
+        };
+
+        legend.render(chunks[7], buf);
+    }
+
+    fn death_cause(&self, cause: DeathCause) -> String {
+        match cause {
+            DeathCause::OutOfLives => "out of lives".to_owned(),
+            DeathCause::LowAccuracy => format!(
+                "accuracy fell below {}%",
+                self.death_mode.and_then(|r| r.min_accuracy).unwrap_or(0.)
+            ),
+            DeathCause::LowWpm => format!(
+                "speed fell below {} wpm",
+                self.death_mode.and_then(|r| r.min_wpm).unwrap_or(0.)
+            ),
+        }
+    }
+
+    /// the results of a finished test as plain sentences, for screen readers
+    /// once the tui has closed
+    pub fn plain_summary(&self) -> String {
+        if let Some(death) = self.death {
+            return format!(
+                "death mode ended the run, {}. {} wpm, {}% accuracy, {} of {} \
+                 words in {:.1}s",
+                self.death_cause(death.cause),
+                death.wpm,
+                death.accuracy,
+                death.words_done,
+                self.total_words(),
+                death.elapsed_secs
+            );
+        }
+
+        let elapsed_secs = self.elapsed().map_or(0.0, |e| e.as_secs_f64());
+        let mut summary = format!(
+            "{} wpm, {}% accuracy, {:.2} standard deviation, {} words in \
+             {:.1}s.",
+            self.wpm,
+            self.accuracy,
+            self.std_dev,
+            self.word_stats().len(),
+            elapsed_secs
+        );
+        let missed_words = self.missed_words();
+        if !missed_words.is_empty() {
+            summary += &format!(" missed: {}.", missed_words.join(", "));
+        }
+        let word_speeds = self.word_speeds();
+        if !word_speeds.is_empty() {
+            summary += &format!(
+                " slowest: {}.",
+                slowest_words(&word_speeds, SLOWEST_WORDS)
+            );
+        }
+
+        summary
+    }
+
+    /// every word typed at a measurable pace with its wpm, in prompt order
+    fn word_speeds(&self) -> Vec<(String, f64)> {
+        self.word_stats()
+            .into_iter()
+            .filter_map(|stats| Some((stats.word.clone(), stats.wpm()?)))
+            .collect()
+    }
+
+    /// a sparkline of how fast each word went, with the slowest ones named
+    /// underneath
+    fn render_word_speeds(
+        &self,
+        word_speeds: &[(String, f64)],
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        if area.height < 2 {
+            return;
+        }
+
+        let data = word_speeds
+            .iter()
+            .map(|(_, wpm)| wpm.round() as u64)
+            .collect::<Vec<u64>>();
+        // one column per word, so short tests get a short sparkline
+        let width = (data.len() as u16).min(area.width);
+        Sparkline::default()
+            .data(&data)
+            .style(MAGENTA_STYLE)
+            .render(
+                Rect {
+                    x: area.x + (area.width - width) / 2,
+                    width,
+                    height: 1,
+                    ..area
+                },
+                buf,
+            );
+
+        // as many of the slowest words as fit
+        let label = "slowest: ";
+        let Some(slowest) = (1..=SLOWEST_WORDS)
+            .rev()
+            .map(|count| slowest_words(word_speeds, count))
+            .find(|s| label.len() + s.chars().count() <= area.width as usize)
+        else {
+            return;
+        };
+        Paragraph::new(Line::from(vec![
+            Span::styled(label, ITALIC_STYLE),
+            Span::styled(slowest, DIM_BOLD_STYLE),
+        ]))
+        .alignment(Alignment::Center)
+        .render(
+            Rect {
+                y: area.y + 1,
+                height: 1,
+                ..area
+            },
+            buf,
+        );
     }
+
+    /// how the focus key of a lesson went and what it means for the next one
+    fn lesson_summary(&self) -> Option<Span<'static>> {
+        let lesson = self.lesson.as_ref()?;
+        let focus = lesson.focus();
+
+        Some(match (lesson.next, lesson.focus_result) {
+            (None, _) => {
+                Span::styled("every key is unlocked", GREEN_BOLD_STYLE)
+            }
+            (Some(next), Some((wpm, accuracy))) if lesson.passed() => {
+                Span::styled(
+                    format!(
+                        "{}: {:.0} wpm {:.0}% acc, unlocked {}",
+                        focus, wpm, accuracy, next
+                    ),
+                    GREEN_BOLD_STYLE,
+                )
+            }
+            (Some(next), Some((wpm, accuracy))) => Span::styled(
+                format!(
+                    "{}: {:.0}/{} wpm {:.0}/{}% acc to unlock {}",
+                    focus,
+                    wpm,
+                    lesson.target_wpm,
+                    accuracy,
+                    lesson.target_accuracy,
+                    next
+                ),
+                BOLD_STYLE,
+            ),
+            (Some(next), None) => Span::styled(
+                format!("type more {} to unlock {}", focus, next),
+                BOLD_STYLE,
+            ),
+        })
+    }
+
+    /// the weak spots in how the fingers and hands were used, empty when too
+    /// little was typed to tell
+    fn fingers_summary(&self) -> String {
+        let usage = self.finger_usage();
+        let mut parts = vec![];
+        if let Some((finger, ms)) = usage.slowest() {
+            parts.push(format!("slowest finger {} ({:.0}ms)", finger, ms));
+        }
+        if let Some((finger, rate)) = usage.least_accurate() {
+            parts.push(format!("most errors {} ({:.0}%)", finger, rate));
+        }
+        if let Some(sfb) = usage.same_finger_bigrams {
+            parts.push(format!("{:.0}% same-finger", sfb));
+        }
+        if let Some(alternation) = usage.hand_alternation {
+            parts.push(format!("{:.0}% alternating", alternation));
+        }
+
+        parts.join("   ")
+    }
+
     fn render_finished_dead(&self, area: Rect, buf: &mut Buffer) {
-        let max_lines = area.height - (VERTICAL_MARGIN * 2);
-        let max_chars_per_line = area.width - (HORIZONTAL_MARGIN * 2);
+        let max_lines = area
+            .height
+            .saturating_sub(VERTICAL_MARGIN * 2 + DEATH_SUMMARY_LINES);
+        let max_chars_per_line =
+            area.width.saturating_sub(HORIZONTAL_MARGIN * 2);
         let chars_per_line;
         let occupied_lines;
         if max_lines * 2 > max_chars_per_line {
@@ -307,49 +945,253 @@ impl Thok<'_> {
             .constraints(
                 [
                     Constraint::Length(
-                        ((area.height as f64 - occupied_lines as f64) / 2.0)
-                            as u16,
+                        area.height.saturating_sub(
+                            occupied_lines + DEATH_SUMMARY_LINES,
+                        ) / 2,
                     ),
                     Constraint::Length(occupied_lines),
-                    Constraint::Length(
-                        ((area.height as f64 - occupied_lines as f64) / 2.0)
-                            as u16,
-                    ),
+                    Constraint::Length(DEATH_SUMMARY_LINES),
+                    Constraint::Min(0),
                 ]
                 .as_ref(),
             )
             .split(area);
 
-        // This should be switched to OnceCell::get_or_try_init once it
-        // is stabilized.
-        if let Some(cache) = self.skull_cache.get() {
-            let widget = Paragraph::new(cache.clone())
-                .alignment(Alignment::Center)
-                .wrap(Wrap { trim: true });
-            widget.render(chunks[1], buf);
-        } else if let Ok(img) =
-            load_image(chars_per_line as u32, occupied_lines as u32)
-        {
-            let skull_strs = img_to_str(img, chars_per_line as usize);
-            let lines: Vec<Line> = skull_strs
-                .into_iter()
-                .map(|i| Line::from(Span::styled(i, RED_BOLD_STYLE)))
-                .collect();
-            let text = Text::from(lines);
-            let _ = self.skull_cache.set(text.clone());
-            let widget = Paragraph::new(text)
+        if let Some(death) = self.death {
+            let summary = Paragraph::new(vec![
+                Line::default(),
+                Line::from(Span::styled(
+                    self.death_cause(death.cause),
+                    RED_BOLD_STYLE,
+                )),
+                Line::from(Span::styled(
+                    format!(
+                        "{} wpm   {}% acc   {}/{} words   {:.1}s",
+                        death.wpm,
+                        death.accuracy,
+                        death.words_done,
+                        self.total_words(),
+                        death.elapsed_secs
+                    ),
+                    BOLD_STYLE,
+                )),
+                Line::default(),
+            ])
+            .alignment(Alignment::Center);
+
+            summary.render(chunks[2], buf);
+        }
+
+        // the skull is only drawn again when the terminal is resized
+        let size = (chars_per_line, occupied_lines);
+        let mut cache = self.skull_cache.borrow_mut();
+        if cache.as_ref().map(|(s, _)| *s) != Some(size) {
+            *cache = (occupied_lines > 0 && !self.accessible)
+                .then(|| {
+                    load_image(chars_per_line as u32, occupied_lines as u32)
+                })
+                .and_then(Result::ok)
+                .map(|img| {
+                    let lines = img_to_str(img, chars_per_line as usize)
+                        .into_iter()
+                        .map(|i| Line::from(Span::styled(i, RED_BOLD_STYLE)))
+                        .collect::<Vec<Line>>();
+                    (size, Text::from(lines))
+                });
+        }
+        if let Some((_, skull)) = cache.as_ref() {
+            Paragraph::new(skull.clone())
                 .alignment(Alignment::Center)
-                .wrap(Wrap { trim: true });
-            widget.render(chunks[1], buf);
-        };
+                .wrap(Wrap { trim: true })
+                .render(chunks[1], buf);
+        }
+
+        let legend = self.legend(
+            &[
+                (Action::Retry, "retry"),
+                (Action::Next, "new"),
+                (Action::Menu, "menu"),
+            ],
+            chunks[3].width,
+        );
+
+        legend.render(chunks[3], buf);
+    }
+
+    /// the death mode rules in play and how close the run is to breaking them
+    fn death_hud(&self, rules: DeathRules) -> String {
+        let mut parts = vec![];
+        if let Some(lives) = self.lives_left() {
+            parts.push(format!(
+                "{} {} left",
+                lives,
+                if lives == 1 { "life" } else { "lives" }
+            ));
+        }
+        if let Some(min) = rules.min_accuracy {
+            parts.push(format!("{}% acc (min {}%)", self.live_accuracy(), min));
+        }
+        if let Some(min) = rules.min_wpm {
+            parts.push(format!("{} wpm (min {})", self.rolling_wpm(), min));
+        }
+
+        parts.join("   ")
+    }
+}
+
+fn finger_color(finger: Option<Finger>) -> Color {
+    match finger {
+        Some(Finger::LeftPinky | Finger::RightPinky) => Color::Magenta,
+        Some(Finger::LeftRing | Finger::RightRing) => Color::Blue,
+        Some(Finger::LeftMiddle | Finger::RightMiddle) => Color::Cyan,
+        Some(Finger::LeftIndex | Finger::RightIndex) => Color::Yellow,
+        Some(Finger::Thumb) | None => Color::DarkGray,
+    }
+}
+
+fn render_too_small(area: Rect, buf: &mut Buffer) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Fill(1),
+                Constraint::Length(2),
+                Constraint::Fill(1),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    let message =
+        Paragraph::new(Span::styled("terminal too small", DIM_BOLD_STYLE))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+
+    message.render(chunks[1], buf);
+}
+
+/// the slowest few distinct words, like `their 31, would 35 wpm`
+fn slowest_words(word_speeds: &[(String, f64)], count: usize) -> String {
+    let mut slowest: Vec<&(String, f64)> = vec![];
+    for word in word_speeds {
+        match slowest.iter_mut().find(|(w, _)| *w == word.0) {
+            Some(seen) if seen.1 > word.1 => *seen = word,
+            Some(_) => {}
+            None => slowest.push(word),
+        }
+    }
+    slowest.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    slowest
+        .iter()
+        .take(count)
+        .map(|(word, wpm)| format!("{} {:.0}", word, wpm))
+        .collect::<Vec<String>>()
+        .join(", ")
+        + " wpm"
+}
+
+/// splits the prompt into lines no wider than `width`, keeping words (and the
+/// space that follows them) together where possible. each line is returned as
+/// a range of char indices into the prompt. `overflow_width` is the width of
+/// anything drawn in front of a char on top of the char itself
+fn wrap_prompt(
+    prompt: &str,
+    width: usize,
+    overflow_width: impl Fn(usize) -> usize,
+) -> Vec<Range<usize>> {
+    let mut lines = vec![];
+    let mut line_start = 0;
+    let mut line_width = 0;
+    let mut word_start = 0;
+    let mut word_width = 0;
+    let mut len = 0;
+
+    for (idx, c) in prompt.chars().enumerate() {
+        let char_width = c.width().unwrap_or(0) + overflow_width(idx);
+
+        if line_width + word_width + char_width > width {
+            if line_width > 0 {
+                // move the word being built onto a fresh line
+                lines.push(line_start..word_start);
+                line_start = word_start;
+                line_width = 0;
+            }
+            if word_width + char_width > width {
+                // the word alone doesn't fit, so it has to be broken up
+                lines.push(line_start..idx);
+                line_start = idx;
+                word_start = idx;
+                word_width = 0;
+            }
+        }
 
-        let legend = Paragraph::new(Span::styled(
-            "(r)etry / (n)ew / (esc)ape",
-            ITALIC_STYLE,
-        ));
+        word_width += char_width;
+        if c == ' ' {
+            line_width += word_width;
+            word_width = 0;
+            word_start = idx + 1;
+        }
+        len = idx + 1;
+    }
 
-        legend.render(chunks[2], buf);
+    if line_start < len {
+        lines.push(line_start..len);
     }
+
+    lines
+}
+
+/// the screen cell of the character at `caret` within the visible prompt
+/// lines drawn into `area`
+fn caret_cell(
+    chars: &[char],
+    overflow_width: impl Fn(usize) -> usize,
+    visible: &[Range<usize>],
+    caret: usize,
+    area: Rect,
+    centered: bool,
+) -> Option<(u16, u16)> {
+    let width = |range: Range<usize>| {
+        range
+            .map(|idx| chars[idx].width().unwrap_or(0) + overflow_width(idx))
+            .sum::<usize>() as u16
+    };
+    // the caret sits past the last character while word strict mode holds
+    // the test open
+    let row = visible.iter().position(|range| {
+        range.contains(&caret) || (caret == chars.len() && range.end == caret)
+    })?;
+    let line = visible[row].clone();
+    // the same offset ratatui gives a centered line
+    let offset = if centered {
+        (area.width / 2).saturating_sub(width(line.clone()) / 2)
+    } else {
+        0
+    };
+    // the caret goes after anything typed past the end of its word
+    let x = area.x
+        + offset
+        + width(line.start..caret)
+        + overflow_width(caret) as u16;
+
+    (x < area.right()).then_some((x, area.y + row as u16))
+}
+
+/// picks the lines to display so the line holding the caret is shown along
+/// with the line before and after it
+fn visible_lines(lines: &[Range<usize>], cursor_pos: usize) -> &[Range<usize>] {
+    let current = lines
+        .iter()
+        .position(|l| l.contains(&cursor_pos))
+        .unwrap_or(lines.len().saturating_sub(1));
+    let visible = VISIBLE_PROMPT_LINES as usize;
+    let first = current
+        .saturating_sub(1)
+        .min(lines.len().saturating_sub(visible));
+    let last = (first + visible).min(lines.len());
+
+    &lines[first..last]
 }
 
 fn load_image(
@@ -395,3 +1237,201 @@ fn img_to_str(
 
 const BRIGHTNESS_CHARS: &str =
     r#"$@B%8&WM#*oahkbdpqwmZO0QLCJUYXzcvunxrjft/\|()1{}[]?-_+~<>i!lI;:,"^`\'."#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_prompt() {
+        assert_eq!(wrap_prompt("one two three", 20, |_| 0), vec![0..13]);
+        assert_eq!(wrap_prompt("one two three", 8, |_| 0), vec![0..8, 8..13]);
+        assert_eq!(
+            wrap_prompt("one two three", 5, |_| 0),
+            vec![0..4, 4..8, 8..13]
+        );
+        assert_eq!(
+            wrap_prompt("abcdefgh ij", 4, |_| 0),
+            vec![0..4, 4..8, 8..11]
+        );
+    }
+
+    #[test]
+    fn test_visible_lines() {
+        let lines = vec![0..4, 4..8, 8..12, 12..16, 16..20];
+        assert_eq!(visible_lines(&lines, 0), &lines[0..3]);
+        assert_eq!(visible_lines(&lines, 5), &lines[0..3]);
+        assert_eq!(visible_lines(&lines, 9), &lines[1..4]);
+        assert_eq!(visible_lines(&lines, 19), &lines[2..5]);
+        assert_eq!(visible_lines(&lines[0..2], 5), &lines[0..2]);
+    }
+
+    #[test]
+    fn test_plain_summary() {
+        let mut thok =
+            Thok::new("ab cd".to_string(), 2, None, None, None, None);
+        "ax cd".chars().for_each(|c| thok.write(c));
+        thok.wpm = 40.0;
+        thok.accuracy = 80.0;
+        let summary = thok.plain_summary();
+        assert!(summary.starts_with("40 wpm, 80% accuracy"));
+        assert!(summary.contains("2 words in"));
+        assert!(summary.contains("missed: ab."));
+
+        let mut thok = Thok::new(
+            "ab cd".to_string(),
+            2,
+            None,
+            None,
+            Some(DeathRules {
+                lives: Some(1),
+                ..Default::default()
+            }),
+            None,
+        );
+        thok.write('x');
+        assert!(thok
+            .plain_summary()
+            .starts_with("death mode ended the run, out of lives."));
+    }
+
+    use crate::config::Config;
+    use crate::keys::KeyBinding;
+    use crate::menu::Screen;
+    use crate::simulate::simulate;
+    use crate::{App, Cli};
+    use clap::Parser;
+    use crossterm::event::KeyEvent;
+    use ratatui::{backend::TestBackend, Terminal};
+    use std::time::SystemTime;
+
+    const PROMPT: &str = "the quick brown fox jumps over the lazy dog";
+
+    /// terminal sizes every screen is drawn at, from far too small to large
+    const WIDTHS: [u16; 14] =
+        [0, 1, 2, 5, 10, 15, 20, 25, 30, 40, 50, 80, 120, 250];
+    const HEIGHTS: [u16; 14] = [0, 1, 2, 3, 4, 5, 6, 8, 10, 12, 16, 24, 40, 80];
+
+    /// `typed` pressed a tenth of a second apart
+    fn keys(typed: &str) -> Vec<(u64, KeyEvent)> {
+        typed
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let name = match c {
+                    ' ' => String::from("space"),
+                    c => c.to_string(),
+                };
+                (i as u64 * 100, name.parse::<KeyBinding>().unwrap().into())
+            })
+            .collect()
+    }
+
+    fn app(screen: Screen) -> App<'static> {
+        let mut app = App::new(
+            Cli::parse_from(["thokr"]),
+            Config::default(),
+            crate::layout::Layout::qwerty(),
+        );
+        app.screen = screen;
+        app
+    }
+
+    /// every screen the tui draws, with one test in each state, on a fixed
+    /// clock so the snapshots don't drift
+    fn screens() -> Vec<(&'static str, App<'static>)> {
+        let in_progress =
+            simulate(PROMPT.into(), &keys("the quikc br"), None, None);
+
+        let mut timed = simulate(PROMPT.into(), &keys("the qu"), None, None);
+        timed.number_of_secs = Some(30.0);
+        timed.seconds_remaining = Some(29.5);
+
+        let mut paced = simulate(PROMPT.into(), &keys("the qu"), None, None);
+        paced.pace = Some(40.0);
+
+        let mut keyboard =
+            simulate(PROMPT.into(), &keys("the quick"), None, None);
+        keyboard.show_keyboard = true;
+        keyboard.show_live_stats = true;
+
+        let finished = simulate(PROMPT.into(), &keys(PROMPT), None, None);
+
+        let mut dead = Thok::new(
+            PROMPT.into(),
+            9,
+            None,
+            None,
+            Some(DeathRules {
+                lives: Some(1),
+                ..Default::default()
+            }),
+            None,
+        );
+        dead.clock = Some(SystemTime::UNIX_EPOCH);
+        "thx".chars().for_each(|c| dead.write(c));
+
+        let mut settings = app(Screen::Settings);
+        settings.selected = 1;
+
+        let mut help = app(Screen::Test);
+        help.thok = simulate(PROMPT.into(), &keys("the quikc br"), None, None);
+        help.show_help = true;
+
+        let tests = [
+            ("in_progress", in_progress),
+            ("timed", timed),
+            ("paced", paced),
+            ("keyboard", keyboard),
+            ("finished", finished),
+            ("dead", dead),
+        ]
+        .into_iter()
+        .map(|(name, thok)| {
+            let mut app = app(Screen::Test);
+            app.thok = thok;
+            (name, app)
+        });
+
+        tests
+            .chain([
+                ("menu", app(Screen::Menu)),
+                ("settings", settings),
+                ("help", help),
+            ])
+            .collect()
+    }
+
+    /// the screen as text followed by where each style starts, so colours
+    /// and the caret show up in snapshots too
+    fn render(app: &mut App, width: u16, height: u16) -> String {
+        let mut terminal =
+            Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| crate::ui(app, f)).unwrap();
+
+        format!("{:#?}", terminal.backend().buffer())
+    }
+
+    #[test]
+    fn test_render_sizes() {
+        for (_, mut app) in screens() {
+            for width in WIDTHS {
+                for height in HEIGHTS {
+                    render(&mut app, width, height);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_snapshots() {
+        for (name, mut app) in screens() {
+            for (width, height) in [(80, 24), (40, 12)] {
+                insta::assert_snapshot!(
+                    format!("{}_{}x{}", name, width, height),
+                    render(&mut app, width, height)
+                );
+            }
+        }
+    }
+}
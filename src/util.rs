@@ -27,6 +27,46 @@ pub fn std_dev(data: &[f64]) -> Option<f64> {
     }
 }
 
+pub fn median(data: &[f64]) -> Option<f64> {
+    percentile(data, 50.0)
+}
+
+/// the value below which `pct` percent of the data falls, interpolating
+/// between the two closest values
+pub fn percentile(data: &[f64], pct: f64) -> Option<f64> {
+    if data.is_empty() {
+        return None;
+    }
+
+    let mut sorted = data.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let rank = (pct.clamp(0.0, 100.0) / 100.0) * (sorted.len() - 1) as f64;
+    let lower = sorted[rank.floor() as usize];
+    let upper = sorted[rank.ceil() as usize];
+
+    Some(lower + (upper - lower) * rank.fract())
+}
+
+/// slope of the least squares line through the data, taking each value's
+/// index as its x position
+pub fn trend(data: &[f64]) -> Option<f64> {
+    if data.len() < 2 {
+        return None;
+    }
+
+    let xs = (0..data.len()).map(|x| x as f64).collect::<Vec<f64>>();
+    let (x_mean, y_mean) = (mean(&xs)?, mean(data)?);
+    let covariance = xs
+        .iter()
+        .zip(data)
+        .map(|(x, y)| (x - x_mean) * (y - y_mean))
+        .sum::<f64>();
+    let variance = xs.iter().map(|x| (x - x_mean).powi(2)).sum::<f64>();
+
+    Some(covariance / variance)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -44,4 +84,27 @@ mod tests {
         );
         assert_eq!(std_dev(&[15., 7., 55.]), Some(20.997354330698162));
     }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[10., 20., 30., 15., 22.]), Some(20.));
+        assert_eq!(median(&[15., 7., 55., 12.]), Some(13.5));
+        assert_eq!(median(&[]), None);
+    }
+
+    #[test]
+    fn test_percentile() {
+        let data = [15., 20., 35., 40., 50.];
+        assert_eq!(percentile(&data, 0.), Some(15.));
+        assert_eq!(percentile(&data, 100.), Some(50.));
+        assert_eq!(percentile(&data, 90.), Some(46.));
+        assert_eq!(percentile(&[7.], 90.), Some(7.));
+    }
+
+    #[test]
+    fn test_trend() {
+        assert_eq!(trend(&[10., 12., 14., 16.]), Some(2.));
+        assert_eq!(trend(&[5., 5., 5.]), Some(0.));
+        assert_eq!(trend(&[5.]), None);
+    }
 }